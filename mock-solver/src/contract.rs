@@ -0,0 +1,73 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use linera_sdk::{
+    base::{ContractAbi, WithContractAbi},
+    views::{RootView, View},
+    Contract, ContractRuntime,
+};
+use mock_solver::Swap;
+use universal_solver::UniversalSolverAbi;
+
+use self::state::MockSolverState;
+
+pub struct MockSolverContract {
+    state: MockSolverState,
+}
+
+linera_sdk::contract!(MockSolverContract);
+
+impl WithContractAbi for MockSolverContract {
+    type Abi = UniversalSolverAbi;
+}
+
+impl Contract for MockSolverContract {
+    type Message = ();
+    type InstantiationArgument = <UniversalSolverAbi as ContractAbi>::Response;
+    type Parameters = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = MockSolverState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        MockSolverContract { state }
+    }
+
+    async fn instantiate(&mut self, response: Self::InstantiationArgument) {
+        let response = bcs::to_bytes(&response).expect("Failed to serialize the swap response");
+        self.state.response.set(response);
+    }
+
+    async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
+        // The solver may support other operations, but sales only request swaps.
+        #[allow(irrefutable_let_patterns)]
+        let universal_solver::Operation::Swap {
+            from_token,
+            to_token,
+            amount,
+            destination_address,
+        } = operation
+        else {
+            panic!("The mock solver only supports swaps");
+        };
+        self.state.swaps.push(Swap {
+            from_token,
+            to_token,
+            amount,
+            destination_address,
+        });
+        bcs::from_bytes(self.state.response.get()).expect("Invalid swap response")
+    }
+
+    async fn execute_message(&mut self, _message: ()) {
+        panic!("The mock solver doesn't send messages");
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
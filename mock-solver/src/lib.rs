@@ -0,0 +1,18 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+/*! A stand-in for the universal solver, used to test the sales of the Non-Fungible Token
+application. It implements the universal solver ABI, records every swap it is asked for, and
+answers each of them with the response it was instantiated with. */
+
+use async_graphql::SimpleObject;
+use serde::{Deserialize, Serialize};
+
+/// A swap requested from the mock solver.
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject)]
+pub struct Swap {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: String,
+    pub destination_address: String,
+}
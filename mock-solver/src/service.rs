@@ -0,0 +1,66 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+mod state;
+
+use std::sync::Arc;
+
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Response, Schema};
+use linera_sdk::{base::WithServiceAbi, views::View, Service, ServiceRuntime};
+use mock_solver::Swap;
+use universal_solver::UniversalSolverAbi;
+
+use self::state::MockSolverState;
+
+pub struct MockSolverService {
+    state: Arc<MockSolverState>,
+}
+
+linera_sdk::service!(MockSolverService);
+
+impl WithServiceAbi for MockSolverService {
+    type Abi = UniversalSolverAbi;
+}
+
+impl Service for MockSolverService {
+    type Parameters = ();
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = MockSolverState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        MockSolverService {
+            state: Arc::new(state),
+        }
+    }
+
+    async fn handle_query(&self, request: Request) -> Response {
+        let schema = Schema::build(
+            QueryRoot {
+                state: self.state.clone(),
+            },
+            EmptyMutation,
+            EmptySubscription,
+        )
+        .finish();
+        schema.execute(request).await
+    }
+}
+
+struct QueryRoot {
+    state: Arc<MockSolverState>,
+}
+
+#[Object]
+impl QueryRoot {
+    /// Returns the swaps requested so far, in order.
+    async fn swaps(&self) -> Vec<Swap> {
+        self.state
+            .swaps
+            .read(..)
+            .await
+            .expect("Failure in retrieving swaps")
+    }
+}
@@ -0,0 +1,15 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_sdk::views::{linera_views, LogView, RegisterView, RootView, ViewStorageContext};
+use mock_solver::Swap;
+
+/// The application state.
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct MockSolverState {
+    // The BCS-encoded response returned for every swap
+    pub response: RegisterView<Vec<u8>>,
+    // The swaps requested so far, in order
+    pub swaps: LogView<Swap>,
+}
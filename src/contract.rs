@@ -7,15 +7,24 @@ mod state;
 
 use std::collections::BTreeSet;
 
-use fungible::Account;
+use fungible::{Account, FungibleOperation, FungibleTokenAbi};
 use linera_sdk::{
-    base::{AccountOwner, WithContractAbi},
-    views::{RootView, View},
+    base::{
+        crypto::Signature, AccountOwner, Amount, ApplicationId, ChainId, CryptoHash, TimeDelta,
+        Timestamp, WithContractAbi,
+    },
+    views::{MapView, RootView, View},
     Contract, ContractRuntime, DataBlobHash,
 };
-use linera_sdk::base::ApplicationId;
-use non_fungible::{Message, Nft, NftStatus, NonFungibleTokenAbi, Operation, TokenId};
+use non_fungible::{
+    BurnPolicy, CollectionCurrency, CollectionMeta, Config, Flag, Listing, MarketEvent,
+    MarketEventKind, Message, MintVoucher, ModerationAction, Nft, NftReceiverAbi,
+    NftReceiverOperation, NftStatus, NonFungibleTokenAbi, Operation, OutboundTransfer, Parameters,
+    PriceStats, RoyaltyMode, SaleSplit, TokenId,
+};
+use serde::{de::DeserializeOwned, Serialize};
 use universal_solver::UniversalSolverAbi;
+
 use self::state::NonFungibleTokenState;
 
 pub struct NonFungibleTokenContract {
@@ -31,8 +40,8 @@ impl WithContractAbi for NonFungibleTokenContract {
 
 impl Contract for NonFungibleTokenContract {
     type Message = Message;
-    type InstantiationArgument = ();
-    type Parameters = ApplicationId<UniversalSolverAbi>;
+    type InstantiationArgument = Config;
+    type Parameters = Parameters;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = NonFungibleTokenState::load(runtime.root_view_storage_context())
@@ -41,9 +50,17 @@ impl Contract for NonFungibleTokenContract {
         NonFungibleTokenContract { state, runtime }
     }
 
-    async fn instantiate(&mut self, _state: Self::InstantiationArgument) {
-        // Validate that the application parameters were configured correctly.
-        self.runtime.application_parameters();
+    async fn instantiate(&mut self, config: Self::InstantiationArgument) {
+        let parameters = self.runtime.application_parameters();
+        assert_ne!(
+            parameters.universal_solver_id.forget_abi(),
+            self.runtime.application_id().forget_abi(),
+            "The universal solver must be another application"
+        );
+        config
+            .check_limits(&parameters.limits)
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.state.config.set(config);
         self.state.num_minted_nfts.set(0);
     }
 
@@ -58,10 +75,36 @@ impl Contract for NonFungibleTokenContract {
                 id,
                 chain_owner,
                 chain_minter,
-                description
+                description,
+                collection,
+                royalty_bps,
+                revealable,
+                list_immediately,
+                soulbound,
             } => {
-                // self.check_account_authentication(minter);
-                self.mint(minter, name, blob_hash, token, price, id, chain_owner, chain_minter, description).await;
+                // The mint window depends on who mints, so it can't trust an unauthenticated
+                // `minter`.
+                if self.state.config.get().mint_start_at.is_some() {
+                    self.check_account_authentication(minter);
+                }
+                self.mint(
+                    minter,
+                    minter,
+                    name,
+                    blob_hash,
+                    token,
+                    price,
+                    id,
+                    chain_minter,
+                    chain_owner,
+                    description,
+                    collection,
+                    royalty_bps,
+                    revealable,
+                    list_immediately,
+                    soulbound,
+                )
+                .await;
             }
 
             Operation::Transfer {
@@ -71,25 +114,111 @@ impl Contract for NonFungibleTokenContract {
                 chain_owner,
                 buy_from_token,
                 to_token,
-                amount
+                amount,
+                memo,
+                royalty_bps_override,
             } => {
+                self.check_memo(memo.as_deref());
                 // self.check_account_authentication(source_owner);
 
                 let mut nft = self.get_nft(&token_id).await;
-                // change chain owner
-                nft.chain_owner = chain_owner.clone();
+                assert_eq!(
+                    nft.status,
+                    NftStatus::OnSale,
+                    "NFT {token_id} is not on sale"
+                );
+                self.check_not_self_transfer(&nft, &target_account);
+                self.check_listing_not_expired(&token_id).await;
+                assert!(
+                    nft.accepts_currency(&to_token),
+                    "NFT is not for sale in {to_token}"
+                );
+                self.select_listing(&mut nft, &to_token).await;
+                nft.check_payment(&to_token, &amount)
+                    .unwrap_or_else(|error| panic!("{error}"));
+                // the seller is paid at their own address, before it's handed to the buyer
+                let seller_chain_owner = nft.chain_owner.clone();
+                // change chain owner, unless none was supplied
+                if let Some(chain_owner) = chain_owner.filter(|chain_owner| !chain_owner.is_empty())
+                {
+                    self.set_chain_owner(&mut nft, chain_owner).await;
+                }
                 // self.check_account_authentication(nft.owner);
+                let universal_solver_id = self.universal_solver_id();
+                let royalty_bps = self.sale_royalty_bps(&nft, royalty_bps_override);
+                let amount = match self.split_sale(&nft, royalty_bps, &amount) {
+                    Some(split) => {
+                        if split.royalty > Amount::ZERO {
+                            let call_royalty_swap = universal_solver::Operation::Swap {
+                                from_token: buy_from_token.clone(),
+                                to_token: to_token.clone(),
+                                amount: split.royalty.to_string(),
+                                destination_address: nft.chain_minter.clone(),
+                            };
+                            self.runtime.call_application(
+                                false,
+                                universal_solver_id,
+                                &call_royalty_swap,
+                            );
+                        }
+                        let fee_shares = self
+                            .state
+                            .config
+                            .get()
+                            .platform_fee_shares(split.platform_fee);
+                        for (share, fee) in fee_shares {
+                            if fee == Amount::ZERO {
+                                continue;
+                            }
+                            assert!(
+                                !share.address.is_empty(),
+                                "Fee recipient {} has no external address",
+                                share.recipient
+                            );
+                            let call_fee_swap = universal_solver::Operation::Swap {
+                                from_token: buy_from_token.clone(),
+                                to_token: to_token.clone(),
+                                amount: fee.to_string(),
+                                destination_address: share.address,
+                            };
+                            self.runtime.call_application(
+                                false,
+                                universal_solver_id,
+                                &call_fee_swap,
+                            );
+                        }
+                        split.seller_proceeds.to_string()
+                    }
+                    None => amount,
+                };
+
                 let call_swap = universal_solver::Operation::Swap {
                     from_token: buy_from_token,
                     to_token,
                     amount,
-                    destination_address: chain_owner.clone(),
+                    destination_address: seller_chain_owner,
                 };
 
-                let universal_solver_id = self.universal_solver_id();
-                self.runtime.call_application(false, universal_solver_id, &call_swap);
+                self.runtime
+                    .call_application(false, universal_solver_id, &call_swap);
+
+                self.record_event_with_memo(
+                    MarketEventKind::Sale,
+                    nft.token_id.clone(),
+                    &[nft.owner, target_account.owner],
+                    memo,
+                )
+                .await;
+                self.transfer(nft, target_account, true).await;
+            }
+
+            Operation::Buy { token_id, payment } => {
+                self.check_account_authentication(payment.owner);
 
-                self.transfer(nft, target_account).await;
+                let nft = self.get_nft(&token_id).await;
+                self.check_not_self_transfer(&nft, &payment);
+                self.check_listing_not_expired(&token_id).await;
+                self.buy(nft, payment).await;
             }
 
             Operation::Claim {
@@ -103,18 +232,273 @@ impl Contract for NonFungibleTokenContract {
                     let nft = self.get_nft(&token_id).await;
                     // self.check_account_authentication(nft.owner);
 
-                    self.transfer(nft, target_account).await;
+                    self.transfer(nft, target_account, false).await;
                 } else {
                     self.remote_claim(source_account, token_id, target_account)
                 }
             }
 
+            Operation::BatchClaim {
+                source_account,
+                token_ids,
+                target_account,
+            } => {
+                self.state
+                    .config
+                    .get()
+                    .check_batch_size(token_ids.len())
+                    .unwrap_or_else(|error| panic!("{error}"));
+
+                if source_account.chain_id == self.runtime.chain_id() {
+                    self.check_account_authentication(source_account.owner);
+                    for token_id in token_ids {
+                        let nft = self.get_nft(&token_id).await;
+                        self.check_account_authentication(nft.owner);
+
+                        self.transfer(nft, target_account, false).await;
+                    }
+                } else {
+                    self.remote_batch_claim(source_account, token_ids, target_account)
+                }
+            }
+
+            Operation::SafeTransfer {
+                source_owner,
+                token_id,
+                target_account,
+                memo,
+            } => {
+                self.check_account_authentication(source_owner);
+                self.check_memo(memo.as_deref());
+
+                let nft = self.get_nft(&token_id).await;
+                self.check_account_authentication(nft.owner);
+                self.check_nft_receiver(&nft, target_account.owner);
+
+                self.record_event_with_memo(
+                    MarketEventKind::Gift,
+                    nft.token_id.clone(),
+                    &[nft.owner, target_account.owner],
+                    memo,
+                )
+                .await;
+                self.transfer(nft, target_account, false).await;
+            }
+
+            Operation::TransferAllToOwner {
+                source_owner,
+                target,
+            } => {
+                self.check_account_authentication(source_owner);
+
+                self.transfer_all_to_owner(source_owner, target).await;
+            }
+
+            Operation::MergeOwner { from, to } => {
+                self.check_account_authentication(from);
+
+                self.merge_owner(from, to).await;
+            }
+
+            Operation::SetStatusForOwned {
+                source_owner,
+                status,
+            } => {
+                self.check_account_authentication(source_owner);
+
+                self.set_status_for_owned(source_owner, status).await;
+            }
+
+            Operation::AdjustPrices {
+                source_owner,
+                factor_bps,
+            } => {
+                self.check_account_authentication(source_owner);
+
+                self.adjust_prices(source_owner, factor_bps).await;
+            }
+
+            Operation::ReclaimStuckTransfer { token_id } => {
+                self.reclaim_stuck_transfer(token_id).await;
+            }
+
+            Operation::Reveal {
+                token_id,
+                new_blob_hash,
+                new_name,
+                new_description,
+            } => {
+                let nft = self.get_nft(&token_id).await;
+                self.check_minter_or_admin_authentication(&nft);
+
+                self.reveal(nft, new_blob_hash, new_name, new_description);
+            }
+
+            Operation::UpdateExternalId { token_id, new_id } => {
+                let nft = self.get_nft(&token_id).await;
+                self.check_owner_or_admin_authentication(&nft);
+
+                self.update_external_id(nft, new_id).await;
+            }
+
+            Operation::SetChainOwner {
+                token_id,
+                chain_owner,
+            } => {
+                let mut nft = self.get_nft(&token_id).await;
+                self.check_account_authentication(nft.owner);
+                self.state
+                    .config
+                    .get()
+                    .check_addresses(&nft.token, &[&chain_owner])
+                    .unwrap_or_else(|error| panic!("{error}"));
+
+                self.set_chain_owner(&mut nft, chain_owner).await;
+                nft.updated_at = self.runtime.system_time();
+                self.state
+                    .nfts
+                    .insert(&token_id, nft)
+                    .expect("Error in insert statement");
+            }
+
+            Operation::SetMinter {
+                token_id,
+                new_minter,
+            } => {
+                self.check_admin_authentication();
+
+                let nft = self.get_nft(&token_id).await;
+                self.set_minter(nft, new_minter).await;
+            }
+
+            Operation::SetCollectionRoyalty {
+                collection,
+                royalty_bps,
+            } => {
+                self.check_admin_authentication();
+                self.state
+                    .config
+                    .get()
+                    .check_royalty(royalty_bps)
+                    .unwrap_or_else(|error| panic!("{error}"));
+
+                self.state
+                    .collection_royalty_bps
+                    .insert(&collection, royalty_bps)
+                    .expect("Error in insert statement");
+            }
+
+            Operation::SetCollectionMeta {
+                collection,
+                name,
+                description,
+                banner_blob_hash,
+            } => {
+                self.set_collection_meta(collection, name, description, banner_blob_hash)
+                    .await;
+            }
+
+            Operation::ReindexAll => {
+                self.check_admin_authentication();
+
+                self.reindex_all().await;
+            }
+
+            Operation::Burn { token_id } => {
+                let nft = self.get_nft(&token_id).await;
+                self.check_burn_authentication(&nft);
+
+                self.burn_nft(nft).await;
+            }
+
+            Operation::SetListingDeadline {
+                token_id,
+                expires_at,
+            } => {
+                let mut nft = self.get_nft(&token_id).await;
+                self.check_account_authentication(nft.owner);
+                assert_eq!(
+                    nft.status,
+                    NftStatus::OnSale,
+                    "NFT {token_id} is not on sale"
+                );
+
+                self.set_listing_expiry(&token_id, Some(expires_at));
+                nft.updated_at = self.runtime.system_time();
+                self.state
+                    .nfts
+                    .insert(&token_id, nft)
+                    .expect("Error in insert statement");
+            }
+
+            Operation::CancelListing { token_id } => {
+                let nft = self.get_nft(&token_id).await;
+                self.check_account_authentication(nft.owner);
+
+                self.cancel_listing(nft).await;
+            }
+
+            Operation::Flag { token_id, reason } => {
+                self.state
+                    .config
+                    .get()
+                    .check_flag_reason(&reason)
+                    .unwrap_or_else(|error| panic!("{error}"));
+                let reporter = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Reports must be authenticated by the reporter");
+                self.get_nft(&token_id).await;
+
+                self.flag(token_id, AccountOwner::User(reporter), reason)
+                    .await;
+            }
+
+            Operation::Moderate { token_id, action } => {
+                self.check_admin_authentication();
+                let nft = self.get_nft(&token_id).await;
+
+                self.moderate(nft, action).await;
+            }
+
+            Operation::Relist {
+                token_id,
+                price,
+                token,
+            } => {
+                let nft = self.get_nft(&token_id).await;
+                self.check_account_authentication(nft.owner);
+
+                self.relist(nft, price, token).await;
+            }
+
+            Operation::RedeemVoucher { voucher, signature } => {
+                let redeemer = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Redeeming a voucher must be authenticated by the redeemer");
+                let signature = bcs::from_bytes::<Signature>(&signature)
+                    .expect("Voucher signature is not a valid signature");
+                signature
+                    .check(&voucher, voucher.minter_public_key)
+                    .expect("Voucher is not signed by its minter");
+
+                self.redeem_voucher(voucher, AccountOwner::User(redeemer))
+                    .await;
+            }
+
             Operation::ListNftForSale {
                 token_id,
-                chain_owner
+                chain_owner,
+                accepted_currencies,
+                listings,
+                expires_at,
             } => {
                 let nft = self.get_nft(&token_id).await;
-                self.list_nft_for_sale(nft, chain_owner).await;
+                self.check_account_authentication(nft.owner);
+
+                self.list_nft_for_sale(nft, chain_owner, accepted_currencies, listings, expires_at)
+                    .await;
             }
         }
     }
@@ -129,24 +513,141 @@ impl Contract for NonFungibleTokenContract {
                     .runtime
                     .message_is_bouncing()
                     .expect("Message delivery status has to be available when executing a message");
-                if !is_bouncing {
+                if is_bouncing {
+                    let was_sale = self
+                        .state
+                        .outbound_transfers
+                        .get(&nft.token_id)
+                        .await
+                        .expect("Failure in retrieving outbound transfer")
+                        .is_some_and(|outbound_transfer| outbound_transfer.is_sale);
+                    self.state
+                        .outbound_transfers
+                        .remove(&nft.token_id)
+                        .expect("Failure removing outbound transfer");
+                    // A bounce must not fail, so a sale still in its relisting cooldown stays
+                    // unlisted.
+                    let relist = was_sale
+                        && self.state.config.get().auto_relist_on_bounce
+                        && self.listing_cooldown_elapsed(&nft.token_id).await;
+                    nft.updated_at = self.runtime.system_time();
+                    if relist {
+                        nft.status = NftStatus::OnSale;
+                        self.track_listing(&nft.token_id).await;
+                        self.charge_listing_fee();
+                        self.record_event(
+                            MarketEventKind::List,
+                            nft.token_id.clone(),
+                            &[nft.owner],
+                        )
+                        .await;
+                    } else {
+                        nft.status = NftStatus::NotListed;
+                    }
+                } else {
+                    self.state
+                        .pending_in
+                        .remove(&nft.token_id)
+                        .expect("Failure removing pending inbound transfer");
                     nft.owner = target_account.owner;
+                    let origin_chain_id = self
+                        .runtime
+                        .message_id()
+                        .expect("Message ID has to be available when executing a message")
+                        .chain_id;
+                    let acknowledgment = Message::TransferReceived {
+                        token_id: nft.token_id.clone(),
+                    };
+                    self.runtime
+                        .prepare_message(acknowledgment)
+                        .send_to(origin_chain_id);
                 }
 
                 self.add_nft(nft).await;
             }
 
+            Message::TransferReceived { token_id } => {
+                let sender_chain_id = self
+                    .runtime
+                    .message_id()
+                    .expect("Message ID has to be available when executing a message")
+                    .chain_id;
+                let outbound_transfer = self
+                    .state
+                    .outbound_transfers
+                    .get(&token_id)
+                    .await
+                    .expect("Failure in retrieving outbound transfer")
+                    .filter(|outbound_transfer| {
+                        outbound_transfer.target_account.chain_id == sender_chain_id
+                    });
+                if let Some(outbound_transfer) = outbound_transfer {
+                    self.state
+                        .outbound_transfers
+                        .remove(&token_id)
+                        .expect("Failure removing outbound transfer");
+                    if outbound_transfer.is_sale {
+                        self.record_sale(
+                            &outbound_transfer.nft,
+                            outbound_transfer.target_account.owner,
+                        )
+                        .await;
+                    }
+                }
+            }
+
             Message::Claim {
                 source_account,
                 token_id,
                 target_account,
             } => {
+                let is_bouncing = self
+                    .runtime
+                    .message_is_bouncing()
+                    .expect("Message delivery status has to be available when executing a message");
+                if is_bouncing {
+                    self.state
+                        .pending_in
+                        .remove(&token_id)
+                        .expect("Failure removing pending inbound transfer");
+                    return;
+                }
+
                 self.check_account_authentication(source_account.owner);
 
                 let nft = self.get_nft(&token_id).await;
                 self.check_account_authentication(nft.owner);
 
-                self.transfer(nft, target_account).await;
+                self.transfer(nft, target_account, false).await;
+            }
+
+            Message::BatchClaim {
+                source_account,
+                token_ids,
+                target_account,
+            } => {
+                let is_bouncing = self
+                    .runtime
+                    .message_is_bouncing()
+                    .expect("Message delivery status has to be available when executing a message");
+                if is_bouncing {
+                    for token_id in &token_ids {
+                        self.state
+                            .pending_in
+                            .remove(token_id)
+                            .expect("Failure removing pending inbound transfer");
+                    }
+                    return;
+                }
+
+                self.check_account_authentication(source_account.owner);
+
+                for token_id in token_ids {
+                    let nft = self.get_nft(&token_id).await;
+                    self.check_account_authentication(nft.owner);
+
+                    self.transfer(nft, target_account, false).await;
+                }
             }
         }
     }
@@ -157,9 +658,8 @@ impl Contract for NonFungibleTokenContract {
 }
 
 impl NonFungibleTokenContract {
-
     fn universal_solver_id(&mut self) -> ApplicationId<UniversalSolverAbi> {
-        self.runtime.application_parameters()
+        self.runtime.application_parameters().universal_solver_id
     }
 
     /// Verifies that a transfer is authenticated for this local account.
@@ -182,15 +682,170 @@ impl NonFungibleTokenContract {
         }
     }
 
-    /// Transfers the specified NFT to another account.
+    /// Returns whether the current operation is authenticated by `owner`.
+    fn is_authenticated(&mut self, owner: AccountOwner) -> bool {
+        match owner {
+            AccountOwner::User(address) => self.runtime.authenticated_signer() == Some(address),
+            AccountOwner::Application(id) => self.runtime.authenticated_caller_id() == Some(id),
+        }
+    }
+
+    /// Verifies that the operation is authenticated by the configured administrator.
+    fn check_admin_authentication(&mut self) {
+        let admin = self
+            .state
+            .config
+            .get()
+            .admin
+            .expect("No administrator is configured");
+        self.check_account_authentication(admin);
+    }
+
+    /// Verifies that the operation is authenticated by the NFT's minter or the administrator.
+    fn check_minter_or_admin_authentication(&mut self, nft: &Nft) {
+        let admin = self.state.config.get().admin;
+        assert!(
+            self.is_authenticated(nft.minter)
+                || admin.is_some_and(|admin| self.is_authenticated(admin)),
+            "The operation must be authenticated by the minter or the administrator."
+        );
+    }
+
+    /// Verifies that the operation is authenticated by an account allowed to burn the NFT
+    /// under the configured burn policy.
+    fn check_burn_authentication(&mut self, nft: &Nft) {
+        match self.state.config.get().burn_policy {
+            BurnPolicy::OwnerOnly => self.check_account_authentication(nft.owner),
+            BurnPolicy::OwnerOrMinter => assert!(
+                self.is_authenticated(nft.owner) || self.is_authenticated(nft.minter),
+                "The operation must be authenticated by the owner or the minter."
+            ),
+        }
+    }
+
+    /// Rejects a transfer memo longer than the configured limit.
+    fn check_memo(&self, memo: Option<&str>) {
+        if let Some(memo) = memo {
+            self.state
+                .config
+                .get()
+                .check_memo(memo)
+                .unwrap_or_else(|error| panic!("{error}"));
+        }
+    }
+
+    /// Verifies that the operation is authenticated by the NFT's owner or the administrator.
+    fn check_owner_or_admin_authentication(&mut self, nft: &Nft) {
+        let admin = self.state.config.get().admin;
+        assert!(
+            self.is_authenticated(nft.owner)
+                || admin.is_some_and(|admin| self.is_authenticated(admin)),
+            "The operation must be authenticated by the owner or the administrator."
+        );
+    }
+
+    /// Returns the royalty rate of a sale of `nft`: the NFT's own rate, or `royalty_bps_override`
+    /// if one is given. Overrides must be authenticated by the NFT's minter and are rejected in
+    /// [`RoyaltyMode::Enforced`] mode.
+    fn sale_royalty_bps(&mut self, nft: &Nft, royalty_bps_override: Option<u16>) -> u16 {
+        let Some(royalty_bps) = royalty_bps_override else {
+            return nft.royalty_bps;
+        };
+        self.state
+            .config
+            .get()
+            .check_royalty_override(royalty_bps)
+            .unwrap_or_else(|error| panic!("{error}"));
+        assert!(
+            self.is_authenticated(nft.minter),
+            "A royalty override must be authenticated by the minter."
+        );
+        royalty_bps
+    }
+
+    /// Splits a sale `amount` into a creator royalty of `royalty_bps` for the NFT's
+    /// `chain_minter`, the configured platform fee and the seller proceeds, which add up to
+    /// exactly the amount. Returns `None` if the amount isn't a valid [`Amount`], in which case
+    /// it all goes to the seller.
+    ///
+    /// A royalty can't be paid if the amount is invalid or the NFT has no `chain_minter` to pay
+    /// it to. Such sales are rejected in [`RoyaltyMode::Enforced`] mode, and go through without
+    /// a royalty in [`RoyaltyMode::Optional`] mode. Sales of an invalid amount are rejected if a
+    /// platform fee is configured, since it can't be taken either.
+    fn split_sale(&self, nft: &Nft, royalty_bps: u16, amount: &str) -> Option<SaleSplit> {
+        let config = self.state.config.get();
+        let total = amount.parse::<Amount>().ok();
+        let royalty_bps = if royalty_bps == 0 || (total.is_some() && !nft.chain_minter.is_empty()) {
+            royalty_bps
+        } else {
+            assert_ne!(
+                config.royalty_mode,
+                RoyaltyMode::Enforced,
+                "The royalty of this sale can't be paid"
+            );
+            0
+        };
+        let Some(total) = total else {
+            assert_eq!(
+                config.platform_fee_bps, 0,
+                "The platform fee of this sale can't be paid"
+            );
+            return None;
+        };
+        Some(SaleSplit::new(total, royalty_bps, config.platform_fee_bps))
+    }
+
+    /// Verifies that an application receiving `nft` accepts it through the
+    /// [`NftReceiverAbi`] hook. User accounts are always accepted.
+    fn check_nft_receiver(&mut self, nft: &Nft, target_owner: AccountOwner) {
+        if let AccountOwner::Application(application_id) = target_owner {
+            let receiver_id = application_id.with_abi::<NftReceiverAbi>();
+            let operation = NftReceiverOperation::OnNftReceived {
+                token_id: nft.token_id.clone(),
+                from: nft.owner,
+            };
+            let accepted = self.runtime.call_application(true, receiver_id, &operation);
+            assert!(accepted, "The receiving application rejected the NFT");
+        }
+    }
+
+    /// Transfers the specified NFT to another account, unless it is soulbound or hidden.
     /// Authentication needs to have happened already.
-    async fn transfer(&mut self, mut nft: Nft, target_account: Account) {
-        self.remove_nft(&nft).await;
+    ///
+    /// If `is_sale` is set, the sale is recorded in the price statistics once the NFT reaches
+    /// the target account, which for cross-chain transfers is when the target chain
+    /// acknowledges it.
+    async fn transfer(&mut self, mut nft: Nft, target_account: Account, is_sale: bool) {
+        check_target_chain(target_account.chain_id);
+        check_not_hidden(&nft);
+        nft.check_transferable()
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.state
+            .config
+            .get()
+            .check_secondary_market(&nft, is_sale)
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.detach_nft(&nft).await;
         nft.status = NftStatus::Sold;
+        nft.updated_at = self.runtime.system_time();
         if target_account.chain_id == self.runtime.chain_id() {
+            if is_sale {
+                self.record_sale(&nft, target_account.owner).await;
+            }
             nft.owner = target_account.owner;
             self.add_nft(nft).await;
         } else {
+            let outbound_transfer = OutboundTransfer {
+                nft: nft.clone(),
+                target_account,
+                sent_at: self.runtime.system_time(),
+                is_sale,
+            };
+            self.state
+                .outbound_transfers
+                .insert(&nft.token_id, outbound_transfer)
+                .expect("Error in insert statement");
+
             let message = Message::Transfer {
                 nft,
                 target_account,
@@ -203,65 +858,776 @@ impl NonFungibleTokenContract {
         }
     }
 
-    async fn get_nft(&self, token_id: &TokenId) -> Nft {
+    /// Adds a completed sale of `nft` at its current price to the statistics of its currency,
+    /// to the volume of its collection, to the volume of its owner as seller and to the volume
+    /// of `buyer`.
+    async fn record_sale(&mut self, nft: &Nft, buyer: AccountOwner) {
+        let Some(price) = nft.price_amount() else {
+            return;
+        };
+        let price_stats = match self
+            .state
+            .price_stats
+            .get(&nft.token)
+            .await
+            .expect("Failure in retrieving price statistics")
+        {
+            Some(mut price_stats) => {
+                price_stats.record(price);
+                price_stats
+            }
+            None => PriceStats::new(price),
+        };
         self.state
-            .nfts
-            .get(token_id)
+            .price_stats
+            .insert(&nft.token, price_stats)
+            .expect("Error in insert statement");
+
+        if !nft.collection.is_empty() {
+            let volume = self
+                .state
+                .collection_volumes
+                .get_mut_or_default(&CollectionCurrency {
+                    collection: nft.collection.clone(),
+                    currency: nft.token.clone(),
+                })
+                .await
+                .expect("Failure in retrieving collection volume");
+            *volume = volume.saturating_add(price);
+        }
+
+        let seller_volume = self
+            .state
+            .seller_volume
+            .get_mut_or_default(&nft.owner)
             .await
-            .expect("Failure in retrieving NFT")
-            .expect("NFT {token_id} not found")
+            .expect("Failure in retrieving seller volume");
+        *seller_volume = seller_volume.saturating_add(price);
+
+        let buyer_volume = self
+            .state
+            .buyer_volume
+            .get_mut_or_default(&buyer)
+            .await
+            .expect("Failure in retrieving buyer volume");
+        *buyer_volume = buyer_volume.saturating_add(price);
     }
 
-    async fn mint(&mut self, owner: AccountOwner, name: String, blob_hash: DataBlobHash,
-                  token: String, // ETH, SOL
-                  price: String, // 0.05 [token]
-                  id: u64, // specific chain nft id
-                  chain_minter: String, // chain nft minter
-                  chain_owner: String,
-                  description: String
-    ) {
-        self.runtime.assert_data_blob_exists(blob_hash);
-        let token_id = Nft::create_token_id(
-            &self.runtime.chain_id(),
-            &self.runtime.application_id().forget_abi(),
-            &name,
-            &owner,
-            &blob_hash,
-            *self.state.num_minted_nfts.get(),
-            &token,
-            price.clone(),
-            id,
-            &chain_owner,
-            &chain_minter
-        )
-        .expect("Failed to serialize NFT metadata");
+    /// Restores an NFT whose cross-chain transfer was neither acknowledged nor bounced within
+    /// `transfer_timeout_secs`.
+    ///
+    /// If the transfer message is still delivered afterwards, the NFT would exist on both
+    /// chains, so the timeout should comfortably exceed the expected delivery delays.
+    async fn reclaim_stuck_transfer(&mut self, token_id: TokenId) {
+        let outbound_transfer = self
+            .state
+            .outbound_transfers
+            .get(&token_id)
+            .await
+            .expect("Failure in retrieving outbound transfer")
+            .expect("No pending transfer for this NFT");
+        self.check_account_authentication(outbound_transfer.nft.owner);
 
-        self.add_nft(Nft {
-            token_id,
-            owner,
-            name,
-            minter: owner,
-            blob_hash,
-            token,
-            price,
-            id,
-            chain_owner,
-            chain_minter,
-            description,
-            status: NftStatus::OnSale,
-        })
-        .await;
+        let timeout_secs = self.state.config.get().transfer_timeout_secs;
+        assert!(
+            self.runtime.system_time()
+                >= outbound_transfer
+                    .sent_at
+                    .saturating_add(TimeDelta::from_secs(timeout_secs)),
+            "The transfer of this NFT can only be reclaimed {timeout_secs} seconds after it was sent"
+        );
 
-        let num_minted_nfts = self.state.num_minted_nfts.get_mut();
-        *num_minted_nfts += 1;
+        self.state
+            .outbound_transfers
+            .remove(&token_id)
+            .expect("Failure removing outbound transfer");
+        self.add_nft(outbound_transfer.nft).await;
     }
 
-    fn remote_claim(
-        &mut self,
-        source_account: Account,
-        token_id: TokenId,
-        target_account: Account,
-    ) {
+    /// Pays the price of an NFT on sale from the `payment` account in the configured fungible
+    /// token, at the NFT's listing in the token's currency, routing the creator royalty to the
+    /// minter, the platform fee to the fee recipients and the rest to the owner, and then
+    /// transfers the NFT to the `payment` account. If any payment fails, the whole purchase is
+    /// rejected.
+    /// Authentication needs to have happened already.
+    async fn buy(&mut self, mut nft: Nft, payment: Account) {
+        let config = self.state.config.get();
+        let payment_token = config
+            .payment_token
+            .expect("No fungible payment token is configured")
+            .with_abi::<FungibleTokenAbi>();
+        let currency = config.payment_currency.clone();
+        assert!(
+            !currency.is_empty(),
+            "No currency is configured for the fungible payment token"
+        );
+        assert_eq!(
+            nft.status,
+            NftStatus::OnSale,
+            "NFT {} is not on sale",
+            nft.token_id
+        );
+        assert!(
+            nft.accepts_currency(&currency),
+            "NFT is not for sale in {currency}"
+        );
+        self.select_listing(&mut nft, &currency).await;
+        nft.check_currency(&currency)
+            .unwrap_or_else(|error| panic!("{error}"));
+        let price = nft
+            .price_amount()
+            .unwrap_or_else(|| panic!("NFT {} has no valid price", nft.token_id));
+
+        let config = self.state.config.get();
+        let split = SaleSplit::new(price, nft.royalty_bps, config.platform_fee_bps);
+        let fee_shares = config.platform_fee_shares(split.platform_fee);
+        self.pay(payment_token, payment.owner, nft.minter, split.royalty);
+        for (share, fee) in fee_shares {
+            self.pay(payment_token, payment.owner, share.recipient, fee);
+        }
+        self.pay(
+            payment_token,
+            payment.owner,
+            nft.owner,
+            split.seller_proceeds,
+        );
+
+        self.record_event(
+            MarketEventKind::Sale,
+            nft.token_id.clone(),
+            &[nft.owner, payment.owner],
+        )
+        .await;
+        self.transfer(nft, payment, true).await;
+    }
+
+    /// Makes `minter` pay the configured mint price in the fungible payment token to the mint
+    /// price recipient. The mint is rejected if the payment fails. Nothing is charged if no
+    /// price, or a zero price, is configured.
+    fn charge_mint_price(&mut self, minter: AccountOwner) {
+        let config = self.state.config.get();
+        let Some(mint_price) = config.mint_price.filter(|price| *price > Amount::ZERO) else {
+            return;
+        };
+        let payment_token = config
+            .payment_token
+            .expect("A mint price is configured without a fungible payment token")
+            .with_abi::<FungibleTokenAbi>();
+        let recipient = config
+            .mint_price_recipient
+            .expect("A mint price is configured without a recipient");
+
+        self.pay(payment_token, minter, recipient, mint_price);
+    }
+
+    /// Transfers `amount` of the fungible `payment_token` from `owner` to `recipient` on the
+    /// current chain. Zero amounts are skipped.
+    fn pay(
+        &mut self,
+        payment_token: ApplicationId<FungibleTokenAbi>,
+        owner: AccountOwner,
+        recipient: AccountOwner,
+        amount: Amount,
+    ) {
+        if amount == Amount::ZERO {
+            return;
+        }
+        let transfer = FungibleOperation::Transfer {
+            owner,
+            amount,
+            target_account: Account {
+                chain_id: self.runtime.chain_id(),
+                owner: recipient,
+            },
+        };
+        self.runtime
+            .call_application(true, payment_token, &transfer);
+    }
+
+    /// Sets the status of all NFTs owned by `owner`, except those that have been sold or
+    /// hidden. The listing fee is charged for every NFT put on sale.
+    /// Authentication needs to have happened already.
+    async fn set_status_for_owned(&mut self, owner: AccountOwner, status: NftStatus) {
+        assert_ne!(
+            status,
+            NftStatus::Sold,
+            "NFTs can only be marked as sold by a sale"
+        );
+        assert_ne!(
+            status,
+            NftStatus::Hidden,
+            "NFTs can only be hidden by moderation"
+        );
+        let token_ids = self
+            .state
+            .owned_token_ids
+            .get(&owner)
+            .await
+            .expect("Failure in retrieving owned NFTs")
+            .unwrap_or_default();
+
+        for token_id in token_ids {
+            let mut nft = self.get_nft(&token_id).await;
+            if matches!(nft.status, NftStatus::Sold | NftStatus::Hidden) || nft.status == status {
+                continue;
+            }
+            if status == NftStatus::OnSale {
+                self.track_listing(&token_id).await;
+                self.charge_listing_fee();
+                self.record_event(MarketEventKind::List, token_id.clone(), &[owner])
+                    .await;
+            } else {
+                self.check_min_listing_duration(&nft).await;
+            }
+            self.update_status(&mut nft, status).await;
+            self.state
+                .nfts
+                .insert(&token_id, nft)
+                .expect("Error in insert statement");
+        }
+    }
+
+    /// Scales the prices of all NFTs on sale owned by `owner`, including their alternative
+    /// listings, by `factor_bps / 10000`. Prices that aren't well-formed amounts are left as is.
+    /// Authentication needs to have happened already.
+    async fn adjust_prices(&mut self, owner: AccountOwner, factor_bps: u32) {
+        let token_ids = self
+            .state
+            .owned_token_ids
+            .get(&owner)
+            .await
+            .expect("Failure in retrieving owned NFTs")
+            .unwrap_or_default();
+
+        for token_id in token_ids {
+            let mut nft = self.get_nft(&token_id).await;
+            if nft.status != NftStatus::OnSale {
+                continue;
+            }
+            nft.price = scale_price(nft.price, factor_bps);
+            nft.updated_at = self.runtime.system_time();
+            if let Some(listings) = self
+                .state
+                .listings
+                .get_mut(&token_id)
+                .await
+                .expect("Failure in retrieving listings")
+            {
+                for listing in listings {
+                    listing.price = scale_price(listing.price.clone(), factor_bps);
+                }
+            }
+            self.state
+                .nfts
+                .insert(&token_id, nft)
+                .expect("Error in insert statement");
+        }
+    }
+
+    /// Reassigns all NFTs of `source_owner` on this chain to `target`.
+    /// Authentication needs to have happened already.
+    async fn transfer_all_to_owner(&mut self, source_owner: AccountOwner, target: AccountOwner) {
+        if source_owner == target {
+            return;
+        }
+        let token_ids = self
+            .state
+            .owned_token_ids
+            .get(&source_owner)
+            .await
+            .expect("Failure in retrieving owned NFTs")
+            .unwrap_or_default();
+
+        let mut nfts = Vec::with_capacity(token_ids.len());
+        for token_id in &token_ids {
+            let nft = self.get_nft(token_id).await;
+            assert_ne!(
+                nft.status,
+                NftStatus::Sold,
+                "NFT {token_id} has been sold and cannot be moved"
+            );
+            check_not_hidden(&nft);
+            nft.check_transferable()
+                .unwrap_or_else(|error| panic!("{error}"));
+            self.state
+                .config
+                .get()
+                .check_secondary_market(&nft, false)
+                .unwrap_or_else(|error| panic!("{error}"));
+            nfts.push(nft);
+        }
+
+        self.reassign_nfts(nfts, source_owner, target).await;
+    }
+
+    /// Reassigns every NFT of `from`, whatever its status, to `to` on the current chain, e.g.
+    /// after a key rotation. Fails if any of them is soulbound, or couldn't be given away under
+    /// the secondary market settings, since `to` could be anyone.
+    /// Authentication needs to have happened already.
+    async fn merge_owner(&mut self, from: AccountOwner, to: AccountOwner) {
+        if from == to {
+            return;
+        }
+        let token_ids = self
+            .state
+            .owned_token_ids
+            .get(&from)
+            .await
+            .expect("Failure in retrieving owned NFTs")
+            .unwrap_or_default();
+
+        let mut nfts = Vec::with_capacity(token_ids.len());
+        for token_id in &token_ids {
+            let nft = self.get_nft(token_id).await;
+            nft.check_transferable()
+                .unwrap_or_else(|error| panic!("{error}"));
+            self.state
+                .config
+                .get()
+                .check_secondary_market(&nft, false)
+                .unwrap_or_else(|error| panic!("{error}"));
+            nfts.push(nft);
+        }
+
+        self.reassign_nfts(nfts, from, to).await;
+    }
+
+    /// Makes `target` the owner of all `nfts`, which must be all those owned by
+    /// `source_owner`, and drops the owner index entry of `source_owner`.
+    async fn reassign_nfts(
+        &mut self,
+        nfts: Vec<Nft>,
+        source_owner: AccountOwner,
+        target: AccountOwner,
+    ) {
+        let now = self.runtime.system_time();
+        for mut nft in nfts {
+            nft.owner = target;
+            nft.updated_at = now;
+            add_to_index(&mut self.state.owned_token_ids, &target, &nft.token_id).await;
+            self.state
+                .nfts
+                .insert(&nft.token_id, nft)
+                .expect("Error in insert statement");
+        }
+        self.state
+            .owned_token_ids
+            .remove(&source_owner)
+            .expect("Failure removing owned NFTs");
+    }
+
+    async fn get_nft(&self, token_id: &TokenId) -> Nft {
+        self.state
+            .nfts
+            .get(token_id)
+            .await
+            .expect("Failure in retrieving NFT")
+            .expect("NFT {token_id} not found")
+    }
+
+    async fn mint(
+        &mut self,
+        minter: AccountOwner,
+        owner: AccountOwner,
+        name: String,
+        blob_hash: DataBlobHash,
+        token: String,        // ETH, SOL
+        price: String,        // 0.05 [token]
+        id: u64,              // specific chain nft id
+        chain_minter: String, // chain nft minter
+        chain_owner: String,
+        description: String,
+        collection: String,
+        royalty_bps: Option<u16>,
+        revealable: bool,
+        list_immediately: bool,
+        soulbound: bool,
+    ) {
+        let royalty_bps = match royalty_bps {
+            Some(royalty_bps) => royalty_bps,
+            None => self
+                .state
+                .collection_royalty_bps
+                .get(&collection)
+                .await
+                .expect("Failure in retrieving collection royalty")
+                .unwrap_or_default(),
+        };
+        assert!(
+            royalty_bps <= 10_000,
+            "Royalty cannot exceed 10000 basis points"
+        );
+        let config = self.state.config.get();
+        config
+            .check_metadata(&name, &description)
+            .unwrap_or_else(|error| panic!("{error}"));
+        config
+            .check_mint_window(&minter, self.runtime.system_time())
+            .unwrap_or_else(|error| panic!("{error}"));
+        config
+            .check_royalty(royalty_bps)
+            .unwrap_or_else(|error| panic!("{error}"));
+        config
+            .check_addresses(&token, &[&chain_minter, &chain_owner])
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.runtime.assert_data_blob_exists(blob_hash);
+        self.charge_mint_price(owner);
+        let token_id = Nft::create_token_id(
+            &self.runtime.chain_id(),
+            &self.runtime.application_id().forget_abi(),
+            &name,
+            &minter,
+            &blob_hash,
+            *self.state.num_minted_nfts.get(),
+            &token,
+            price.clone(),
+            id,
+            &chain_owner,
+            &chain_minter,
+            self.runtime.system_time(),
+            soulbound,
+        )
+        .expect("Failed to serialize NFT metadata");
+
+        let status = if list_immediately {
+            NftStatus::OnSale
+        } else {
+            NftStatus::NotListed
+        };
+        self.record_event(MarketEventKind::Mint, token_id.clone(), &[minter, owner])
+            .await;
+        self.add_nft(Nft {
+            token_id,
+            owner,
+            name,
+            minter,
+            blob_hash,
+            token,
+            price,
+            id,
+            chain_owner,
+            chain_minter,
+            description,
+            collection,
+            status,
+            accepted_currencies: None,
+            royalty_bps,
+            revealable,
+            revealed: false,
+            updated_at: self.runtime.system_time(),
+            soulbound,
+        })
+        .await;
+
+        let num_minted_nfts = self.state.num_minted_nfts.get_mut();
+        *num_minted_nfts += 1;
+    }
+
+    /// Sets the display metadata of a collection. If it has no metadata yet, the signer must be
+    /// the administrator or the minter of one of its NFTs, and becomes its creator. Updates must
+    /// be authenticated by the creator or the administrator.
+    async fn set_collection_meta(
+        &mut self,
+        collection: String,
+        name: String,
+        description: String,
+        banner_blob_hash: Option<DataBlobHash>,
+    ) {
+        let creator = match self
+            .state
+            .collection_metadata
+            .get(&collection)
+            .await
+            .expect("Failure in retrieving collection metadata")
+        {
+            Some(meta) => {
+                let admin = self.state.config.get().admin;
+                assert!(
+                    self.is_authenticated(meta.creator)
+                        || admin.is_some_and(|admin| self.is_authenticated(admin)),
+                    "The operation must be authenticated by the collection creator or the \
+                     administrator."
+                );
+                meta.creator
+            }
+            None => {
+                let signer = AccountOwner::User(
+                    self.runtime
+                        .authenticated_signer()
+                        .expect("Setting collection metadata must be authenticated"),
+                );
+                let is_admin = self.state.config.get().admin == Some(signer);
+                assert!(
+                    is_admin || self.is_collection_minter(&collection, signer).await,
+                    "Only the administrator or a minter of the collection can set its metadata \
+                     first"
+                );
+                signer
+            }
+        };
+        self.state
+            .config
+            .get()
+            .check_metadata(&name, &description)
+            .unwrap_or_else(|error| panic!("{error}"));
+        if let Some(banner_blob_hash) = banner_blob_hash {
+            self.runtime.assert_data_blob_exists(banner_blob_hash);
+        }
+
+        let meta = CollectionMeta {
+            name,
+            description,
+            banner_blob_hash,
+            creator,
+        };
+        self.state
+            .collection_metadata
+            .insert(&collection, meta)
+            .expect("Error in insert statement");
+    }
+
+    /// Returns whether `account` minted one of the NFTs of `collection` held on this chain.
+    async fn is_collection_minter(&mut self, collection: &String, account: AccountOwner) -> bool {
+        let token_ids = self
+            .state
+            .collection_token_ids
+            .get(collection)
+            .await
+            .expect("Failure in retrieving collection tokens")
+            .unwrap_or_default();
+        for token_id in token_ids {
+            if self.get_nft(&token_id).await.minter == account {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Mints the NFT described by a voucher to `redeemer`, rejecting vouchers issued for another
+    /// chain or application, or that have already been redeemed. The signature needs to have
+    /// been checked already.
+    async fn redeem_voucher(&mut self, voucher: MintVoucher, redeemer: AccountOwner) {
+        assert_eq!(
+            voucher.chain_id,
+            self.runtime.chain_id(),
+            "Voucher is for another chain"
+        );
+        assert_eq!(
+            voucher.application_id,
+            self.runtime.application_id().forget_abi(),
+            "Voucher is for another application"
+        );
+        let minter = voucher.minter();
+        let redeemed_nonces = self
+            .state
+            .redeemed_vouchers
+            .get_mut_or_default(&minter)
+            .await
+            .expect("Failure in retrieving redeemed vouchers");
+        assert!(
+            redeemed_nonces.insert(voucher.nonce),
+            "Voucher {} of {minter} has already been redeemed",
+            voucher.nonce
+        );
+
+        self.mint(
+            minter,
+            redeemer,
+            voucher.name,
+            voucher.blob_hash,
+            voucher.token,
+            voucher.price,
+            voucher.id,
+            voucher.chain_minter,
+            voucher.chain_owner,
+            voucher.description,
+            voucher.collection,
+            voucher.royalty_bps,
+            voucher.revealable,
+            false,
+            voucher.soulbound,
+        )
+        .await;
+    }
+
+    /// Replaces the blob and metadata of a revealable NFT in place, once.
+    /// Authentication needs to have happened already.
+    fn reveal(
+        &mut self,
+        mut nft: Nft,
+        new_blob_hash: DataBlobHash,
+        new_name: String,
+        new_description: String,
+    ) {
+        assert!(nft.revealable, "NFT {} is not revealable", nft.token_id);
+        assert!(
+            !nft.revealed,
+            "NFT {} has already been revealed",
+            nft.token_id
+        );
+        self.state
+            .config
+            .get()
+            .check_metadata(&new_name, &new_description)
+            .unwrap_or_else(|error| panic!("{error}"));
+        self.runtime.assert_data_blob_exists(new_blob_hash);
+
+        nft.blob_hash = new_blob_hash;
+        nft.name = new_name;
+        nft.description = new_description;
+        nft.revealed = true;
+        nft.updated_at = self.runtime.system_time();
+        self.state
+            .nfts
+            .insert(&nft.token_id, nft)
+            .expect("Error in insert statement");
+    }
+
+    /// Moves the specified NFT to a new external chain ID, re-keying its `blob_token_ids` entry.
+    async fn update_external_id(&mut self, mut nft: Nft, new_id: u64) {
+        if nft.id == new_id {
+            return;
+        }
+        assert!(
+            !self
+                .state
+                .blob_token_ids
+                .contains_key(&new_id)
+                .await
+                .expect("Failure in retrieving external id"),
+            "External id {new_id} is already mapped to another NFT"
+        );
+
+        let old_token_id = self
+            .state
+            .blob_token_ids
+            .get(&nft.id)
+            .await
+            .expect("Failure in retrieving external id");
+        if old_token_id.as_ref() == Some(&nft.token_id) {
+            self.state
+                .blob_token_ids
+                .remove(&nft.id)
+                .expect("Error in remove statement");
+        }
+        self.state
+            .blob_token_ids
+            .insert(&new_id, nft.token_id.clone())
+            .expect("Error in insert statement");
+
+        nft.id = new_id;
+        nft.updated_at = self.runtime.system_time();
+        self.state
+            .nfts
+            .insert(&nft.token_id, nft)
+            .expect("Error in insert statement");
+    }
+
+    /// Credits `new_minter` as the creator of the specified NFT.
+    /// Authentication needs to have happened already.
+    async fn set_minter(&mut self, mut nft: Nft, new_minter: AccountOwner) {
+        assert!(
+            self.state.config.get().minter_reassignable,
+            "Minters are not reassignable in this application"
+        );
+        remove_from_index(&mut self.state.minter_token_ids, &nft.minter, &nft.token_id).await;
+        add_to_index(&mut self.state.minter_token_ids, &new_minter, &nft.token_id).await;
+        nft.minter = new_minter;
+        nft.updated_at = self.runtime.system_time();
+        self.state
+            .nfts
+            .insert(&nft.token_id, nft)
+            .expect("Error in insert statement");
+    }
+
+    /// Rebuilds the owner, collection, minter, status and external ID indexes from the `nfts` map,
+    /// discarding any entries that drifted from it.
+    async fn reindex_all(&mut self) {
+        let mut nfts = Vec::new();
+        self.state
+            .nfts
+            .for_each_index_value(|_token_id, nft| {
+                nfts.push(nft.into_owned());
+                Ok(())
+            })
+            .await
+            .expect("Failure in retrieving NFTs");
+
+        self.state.owned_token_ids.clear();
+        self.state.collection_token_ids.clear();
+        self.state.minter_token_ids.clear();
+        self.state.chain_owner_token_ids.clear();
+        self.state.status_token_ids.clear();
+        self.state.blob_token_ids.clear();
+        for nft in nfts {
+            self.add_nft(nft).await;
+        }
+    }
+
+    /// Permanently removes the specified NFT, counting it as burned.
+    /// Authentication needs to have happened already.
+    async fn burn_nft(&mut self, nft: Nft) {
+        self.detach_nft(&nft).await;
+        self.record_event(MarketEventKind::Burn, nft.token_id, &[nft.owner])
+            .await;
+
+        let num_burned = self.state.num_burned.get_mut();
+        *num_burned += 1;
+    }
+
+    /// Appends an event to the marketplace log, and indexes it under each of the `owners`
+    /// involved in it.
+    async fn record_event(
+        &mut self,
+        kind: MarketEventKind,
+        token_id: TokenId,
+        owners: &[AccountOwner],
+    ) {
+        self.record_event_with_memo(kind, token_id, owners, None)
+            .await;
+    }
+
+    /// Same as `record_event`, attaching the sender's `memo` to the event.
+    async fn record_event_with_memo(
+        &mut self,
+        kind: MarketEventKind,
+        token_id: TokenId,
+        owners: &[AccountOwner],
+        memo: Option<String>,
+    ) {
+        let sequence = self.state.events.count() as u64 + 1;
+        self.state.events.push(MarketEvent {
+            sequence,
+            kind,
+            token_id,
+            timestamp: self.runtime.system_time(),
+            memo,
+        });
+
+        for owner in owners.iter().collect::<BTreeSet<_>>() {
+            let owner_events = self
+                .state
+                .owner_events
+                .get_mut_or_default(owner)
+                .await
+                .expect("Failure in retrieving owner events");
+            owner_events.push(sequence);
+        }
+    }
+
+    fn remote_claim(
+        &mut self,
+        source_account: Account,
+        token_id: TokenId,
+        target_account: Account,
+    ) {
+        if target_account.chain_id == self.runtime.chain_id() {
+            let now = self.runtime.system_time();
+            self.state
+                .pending_in
+                .insert(&token_id, now)
+                .expect("Error in insert statement");
+        }
         let message = Message::Claim {
             source_account,
             token_id,
@@ -270,6 +1636,36 @@ impl NonFungibleTokenContract {
         self.runtime
             .prepare_message(message)
             .with_authentication()
+            .with_tracking()
+            .send_to(source_account.chain_id);
+    }
+
+    /// Asks the remote chain of `source_account` to transfer several of its NFTs to
+    /// `target_account` with a single message.
+    fn remote_batch_claim(
+        &mut self,
+        source_account: Account,
+        token_ids: Vec<TokenId>,
+        target_account: Account,
+    ) {
+        if target_account.chain_id == self.runtime.chain_id() {
+            let now = self.runtime.system_time();
+            for token_id in &token_ids {
+                self.state
+                    .pending_in
+                    .insert(token_id, now)
+                    .expect("Error in insert statement");
+            }
+        }
+        let message = Message::BatchClaim {
+            source_account,
+            token_ids,
+            target_account,
+        };
+        self.runtime
+            .prepare_message(message)
+            .with_authentication()
+            .with_tracking()
             .send_to(source_account.chain_id);
     }
 
@@ -277,6 +1673,8 @@ impl NonFungibleTokenContract {
         let token_id = nft.token_id.clone();
         let owner = nft.owner;
         let _id = nft.id;
+        let collection = nft.collection.clone();
+        let status = nft.status;
 
         self.state
             .nfts
@@ -299,43 +1697,1529 @@ impl NonFungibleTokenContract {
                 .expect("Error in insert statement");
         }
 
-         self
-        .state
-        .blob_token_ids
-        .insert(&_id, nft.token_id.clone())
-        .expect("Error in get_mut statement")
+        if !collection.is_empty() {
+            add_to_index(&mut self.state.collection_token_ids, &collection, &token_id).await;
+        }
+        add_to_index(&mut self.state.minter_token_ids, &nft.minter, &token_id).await;
+        if !nft.chain_owner.is_empty() {
+            add_to_index(
+                &mut self.state.chain_owner_token_ids,
+                &nft.chain_owner,
+                &token_id,
+            )
+            .await;
+        }
+        add_to_index(&mut self.state.status_token_ids, &status, &token_id).await;
 
+        self.state
+            .blob_token_ids
+            .insert(&_id, nft.token_id.clone())
+            .expect("Error in get_mut statement")
     }
 
-    async fn list_nft_for_sale(&mut self, mut nft: Nft, chain_owner: String){
-        nft.status = NftStatus::OnSale;
-        nft.chain_owner = chain_owner;
+    async fn list_nft_for_sale(
+        &mut self,
+        mut nft: Nft,
+        chain_owner: String,
+        accepted_currencies: Option<BTreeSet<String>>,
+        listings: Option<Vec<Listing>>,
+        expires_at: Option<Timestamp>,
+    ) {
+        check_not_hidden(&nft);
+        self.check_min_listing_duration(&nft).await;
+        self.track_listing(&nft.token_id).await;
+        self.charge_listing_fee();
+        self.update_status(&mut nft, NftStatus::OnSale).await;
+        self.set_chain_owner(&mut nft, chain_owner).await;
+        nft.accepted_currencies = accepted_currencies;
+        self.set_listings(&mut nft, listings.unwrap_or_default());
+        self.set_listing_expiry(&nft.token_id, expires_at);
+        self.record_event(MarketEventKind::List, nft.token_id.clone(), &[nft.owner])
+            .await;
         self.state
             .nfts
             .insert(&nft.token_id, nft.clone())
             .expect("Error in insert statement");
     }
 
-    async fn remove_nft(&mut self, nft: &Nft) {
+    /// Puts an NFT back on sale at the given price.
+    /// Authentication needs to have happened already.
+    async fn relist(&mut self, mut nft: Nft, price: String, token: String) {
+        check_not_hidden(&nft);
+        self.check_min_listing_duration(&nft).await;
+        self.track_listing(&nft.token_id).await;
+        self.charge_listing_fee();
+        self.update_status(&mut nft, NftStatus::OnSale).await;
+        self.set_listings(&mut nft, Vec::new());
+        self.set_listing_expiry(&nft.token_id, None);
+        nft.price = price;
+        nft.token = token;
+        self.record_event(MarketEventKind::List, nft.token_id.clone(), &[nft.owner])
+            .await;
         self.state
             .nfts
-            .remove(&nft.token_id)
-            .expect("Failure removing NFT");
-        let owned_token_ids = self
-            .state
-            .owned_token_ids
-            .get_mut(&nft.owner)
-            .await
-            .expect("Error in get_mut statement")
-            .expect("NFT set should be there!");
-
-        owned_token_ids.remove(&nft.token_id);
+            .insert(&nft.token_id, nft.clone())
+            .expect("Error in insert statement");
+    }
+
+    /// Routes the configured listing fee to the fee recipient through the universal solver.
+    /// The listing is rejected if the solver can't complete the payment. Nothing is charged
+    /// if no fee, or a zero fee, is configured.
+    fn charge_listing_fee(&mut self) {
+        let config = self.state.config.get();
+        let Some(listing_fee) = config.listing_fee.clone().filter(|listing_fee| {
+            listing_fee
+                .parse::<Amount>()
+                .map_or(!listing_fee.is_empty(), |listing_fee| {
+                    listing_fee > Amount::ZERO
+                })
+        }) else {
+            return;
+        };
+        assert!(
+            !config.fee_recipient.is_empty(),
+            "A listing fee is configured without a fee recipient"
+        );
+
+        let call_fee_swap = universal_solver::Operation::Swap {
+            from_token: config.listing_fee_token.clone(),
+            to_token: config.listing_fee_token.clone(),
+            amount: listing_fee,
+            destination_address: config.fee_recipient.clone(),
+        };
+        let universal_solver_id = self.universal_solver_id();
+        self.runtime
+            .call_application(false, universal_solver_id, &call_fee_swap);
+    }
+
+    /// Records a moderation report of `token_id` by `reporter`, replacing any earlier report
+    /// of theirs so that each reporter only adds one entry.
+    async fn flag(&mut self, token_id: TokenId, reporter: AccountOwner, reason: String) {
+        let flag = Flag {
+            reporter,
+            reason,
+            flagged_at: self.runtime.system_time(),
+        };
+        let flags = self
+            .state
+            .flags
+            .get_mut_or_default(&token_id)
+            .await
+            .expect("Failure in retrieving flags");
+        flags.retain(|flag| flag.reporter != reporter);
+        flags.push(flag);
+    }
+
+    /// Applies a moderation `action` to an NFT.
+    /// Authentication needs to have happened already.
+    async fn moderate(&mut self, mut nft: Nft, action: ModerationAction) {
+        match action {
+            ModerationAction::Hide => {
+                if nft.status == NftStatus::Hidden {
+                    return;
+                }
+                self.update_status(&mut nft, NftStatus::Hidden).await;
+                self.set_listings(&mut nft, Vec::new());
+                self.set_listing_expiry(&nft.token_id, None);
+            }
+            ModerationAction::Unhide => {
+                assert_eq!(
+                    nft.status,
+                    NftStatus::Hidden,
+                    "NFT {} is not hidden",
+                    nft.token_id
+                );
+                self.update_status(&mut nft, NftStatus::NotListed).await;
+            }
+            ModerationAction::ClearFlags => {
+                self.state
+                    .flags
+                    .remove(&nft.token_id)
+                    .expect("Failure removing flags");
+                return;
+            }
+        }
+        self.state
+            .nfts
+            .insert(&nft.token_id, nft)
+            .expect("Error in insert statement");
+    }
+
+    /// Takes an NFT off sale, dropping its alternative prices and expiry.
+    /// Authentication needs to have happened already.
+    async fn cancel_listing(&mut self, mut nft: Nft) {
+        assert_eq!(
+            nft.status,
+            NftStatus::OnSale,
+            "NFT {} is not on sale",
+            nft.token_id
+        );
+        self.check_min_listing_duration(&nft).await;
+        self.update_status(&mut nft, NftStatus::NotListed).await;
+        self.set_listings(&mut nft, Vec::new());
+        self.set_listing_expiry(&nft.token_id, None);
+        self.state
+            .nfts
+            .insert(&nft.token_id, nft)
+            .expect("Error in insert statement");
+    }
+
+    /// Rejects cancelling or replacing the listing of an NFT on sale before it has been listed
+    /// for the configured minimum duration, which prevents flash listings.
+    async fn check_min_listing_duration(&mut self, nft: &Nft) {
+        if nft.status != NftStatus::OnSale {
+            return;
+        }
+        let min_duration_secs = self.state.config.get().min_listing_duration_secs;
+        if let Some(last_listed_at) = self
+            .state
+            .last_listed_at
+            .get(&nft.token_id)
+            .await
+            .expect("Failure in retrieving listing time")
+        {
+            assert!(
+                self.runtime.system_time()
+                    >= last_listed_at.saturating_add(TimeDelta::from_secs(min_duration_secs)),
+                "NFT must stay listed for at least {min_duration_secs} seconds"
+            );
+        }
+    }
+
+    /// Rejects listing an NFT again before the configured cooldown has elapsed since it was
+    /// last listed, which discourages wash trading. Records the current time as the latest
+    /// listing otherwise.
+    async fn track_listing(&mut self, token_id: &TokenId) {
+        let cooldown_secs = self.state.config.get().relist_cooldown_secs;
+        assert!(
+            self.listing_cooldown_elapsed(token_id).await,
+            "NFT was listed less than {cooldown_secs} seconds ago"
+        );
+        let now = self.runtime.system_time();
+        self.state
+            .last_listed_at
+            .insert(token_id, now)
+            .expect("Error in insert statement");
+    }
+
+    /// Returns whether the configured cooldown has elapsed since the NFT was last listed, if
+    /// it ever was.
+    async fn listing_cooldown_elapsed(&mut self, token_id: &TokenId) -> bool {
+        let now = self.runtime.system_time();
+        let cooldown_secs = self.state.config.get().relist_cooldown_secs;
+        let last_listed_at = self
+            .state
+            .last_listed_at
+            .get(token_id)
+            .await
+            .expect("Failure in retrieving listing time");
+        last_listed_at.map_or(true, |last_listed_at| {
+            now >= last_listed_at.saturating_add(TimeDelta::from_secs(cooldown_secs))
+        })
+    }
+
+    /// Replaces the alternative prices of a listed NFT. The first listing, if any, also becomes
+    /// the NFT's `price` and `token`, so that clients reading a single price keep working.
+    fn set_listings(&mut self, nft: &mut Nft, listings: Vec<Listing>) {
+        let Some(first_listing) = listings.first() else {
+            self.state
+                .listings
+                .remove(&nft.token_id)
+                .expect("Failure removing listings");
+            return;
+        };
+        nft.price = first_listing.price.clone();
+        nft.token = first_listing.currency.clone();
+        self.state
+            .listings
+            .insert(&nft.token_id, listings)
+            .expect("Error in insert statement");
+    }
+
+    /// Records when the current listing of an NFT expires, or that it doesn't.
+    fn set_listing_expiry(&mut self, token_id: &TokenId, expires_at: Option<Timestamp>) {
+        if let Some(expires_at) = expires_at {
+            self.state
+                .listing_expiries
+                .insert(token_id, expires_at)
+                .expect("Error in insert statement");
+        } else {
+            self.state
+                .listing_expiries
+                .remove(token_id)
+                .expect("Failure removing listing expiry");
+        }
+    }
+
+    /// Rejects selling an NFT to the account that already owns it. Such a sale would be a
+    /// no-op apart from paying for the swap and marking the NFT as sold, so it is treated as
+    /// an error rather than silently skipped.
+    fn check_not_self_transfer(&mut self, nft: &Nft, target_account: &Account) {
+        assert!(
+            target_account.chain_id != self.runtime.chain_id() || target_account.owner != nft.owner,
+            "NFT {} is already owned by the target account",
+            nft.token_id
+        );
+    }
+
+    /// Rejects buying an NFT whose time-limited listing has expired.
+    async fn check_listing_not_expired(&mut self, token_id: &TokenId) {
+        let expires_at = self
+            .state
+            .listing_expiries
+            .get(token_id)
+            .await
+            .expect("Failure in retrieving listing expiry");
+        if let Some(expires_at) = expires_at {
+            assert!(
+                self.runtime.system_time() <= expires_at,
+                "The listing of NFT {token_id} expired at {expires_at}"
+            );
+        }
+    }
+
+    /// Picks the listing of `nft` in the currency the seller will be paid in, and makes it the
+    /// NFT's price. NFTs listed with a single price are left unchanged.
+    async fn select_listing(&mut self, nft: &mut Nft, currency: &str) {
+        let Some(listings) = self
+            .state
+            .listings
+            .get(&nft.token_id)
+            .await
+            .expect("Failure in retrieving listings")
+        else {
+            return;
+        };
+        let listing = listings
+            .into_iter()
+            .find(|listing| listing.currency == currency)
+            .unwrap_or_else(|| panic!("NFT has no listing in {currency}"));
+        nft.price = listing.price;
+        nft.token = listing.currency;
+    }
+
+    /// Changes the external chain owner of a stored NFT, keeping the chain owner index up to
+    /// date. The caller is responsible for saving the updated NFT.
+    async fn set_chain_owner(&mut self, nft: &mut Nft, chain_owner: String) {
+        if !nft.chain_owner.is_empty() {
+            remove_from_index(
+                &mut self.state.chain_owner_token_ids,
+                &nft.chain_owner,
+                &nft.token_id,
+            )
+            .await;
+        }
+        if !chain_owner.is_empty() {
+            add_to_index(
+                &mut self.state.chain_owner_token_ids,
+                &chain_owner,
+                &nft.token_id,
+            )
+            .await;
+        }
+        nft.chain_owner = chain_owner;
+    }
 
-            self
+    /// Changes the status of a stored NFT, keeping the status index up to date, and marks it as
+    /// recently active. The caller is responsible for saving the updated NFT.
+    async fn update_status(&mut self, nft: &mut Nft, status: NftStatus) {
+        remove_from_index(&mut self.state.status_token_ids, &nft.status, &nft.token_id).await;
+        nft.status = status;
+        nft.updated_at = self.runtime.system_time();
+        add_to_index(&mut self.state.status_token_ids, &status, &nft.token_id).await;
+    }
+
+    /// Removes the NFT and its index entries from this chain, without touching burn
+    /// statistics, so that it can be re-added here or on another chain.
+    async fn detach_nft(&mut self, nft: &Nft) {
+        self.state
+            .nfts
+            .remove(&nft.token_id)
+            .expect("Failure removing NFT");
+        self.state
+            .listings
+            .remove(&nft.token_id)
+            .expect("Failure removing listings");
+        self.set_listing_expiry(&nft.token_id, None);
+        let owned_token_ids = self
             .state
+            .owned_token_ids
+            .get_mut(&nft.owner)
+            .await
+            .expect("Error in get_mut statement")
+            .expect("NFT set should be there!");
+
+        owned_token_ids.remove(&nft.token_id);
+
+        if !nft.collection.is_empty() {
+            remove_from_index(
+                &mut self.state.collection_token_ids,
+                &nft.collection,
+                &nft.token_id,
+            )
+            .await;
+        }
+        remove_from_index(&mut self.state.minter_token_ids, &nft.minter, &nft.token_id).await;
+        if !nft.chain_owner.is_empty() {
+            remove_from_index(
+                &mut self.state.chain_owner_token_ids,
+                &nft.chain_owner,
+                &nft.token_id,
+            )
+            .await;
+        }
+        remove_from_index(&mut self.state.status_token_ids, &nft.status, &nft.token_id).await;
+
+        self.state
             .blob_token_ids
             .remove(&nft.id)
             .expect("Error in get_mut statement")
+    }
+}
+
+/// Multiplies a price by `factor_bps / 10000`, panicking on overflow. Prices that aren't
+/// well-formed amounts are returned unchanged.
+fn scale_price(price: String, factor_bps: u32) -> String {
+    let Ok(amount) = price.parse::<Amount>() else {
+        return price;
+    };
+    let attos = u128::from(amount)
+        .checked_mul(u128::from(factor_bps))
+        .expect("Price adjustment overflows")
+        / 10_000;
+    Amount::from_attos(attos).to_string()
+}
+
+/// Adds `token_id` to the set stored under `key` in a secondary index.
+async fn add_to_index<K>(index: &mut MapView<K, BTreeSet<TokenId>>, key: &K, token_id: &TokenId)
+where
+    K: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    if let Some(token_ids) = index
+        .get_mut(key)
+        .await
+        .expect("Error in get_mut statement")
+    {
+        token_ids.insert(token_id.clone());
+    } else {
+        index
+            .insert(key, BTreeSet::from([token_id.clone()]))
+            .expect("Error in insert statement");
+    }
+}
+
+/// Removes `token_id` from the set stored under `key` in a secondary index, dropping the
+/// entry once its set is empty.
+async fn remove_from_index<K>(
+    index: &mut MapView<K, BTreeSet<TokenId>>,
+    key: &K,
+    token_id: &TokenId,
+) where
+    K: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    let Some(token_ids) = index
+        .get_mut(key)
+        .await
+        .expect("Error in get_mut statement")
+    else {
+        return;
+    };
+    token_ids.remove(token_id);
+    if token_ids.is_empty() {
+        index.remove(key).expect("Failure removing index entry");
+    }
+}
+
+/// Rejects listing or moving an NFT taken down by moderation, which would undo the takedown.
+fn check_not_hidden(nft: &Nft) {
+    assert_ne!(
+        nft.status,
+        NftStatus::Hidden,
+        "NFT {} has been hidden by moderation",
+        nft.token_id
+    );
+}
+
+/// Rejects transfers to a chain ID that can't belong to a real chain, so that NFTs aren't sent
+/// into the void. Chain IDs are hashes of their creation description, so the all-zero ID
+/// used as a placeholder or default value by clients never identifies an actual chain.
+fn check_target_chain(chain_id: ChainId) {
+    assert_ne!(
+        chain_id,
+        ChainId(CryptoHash::from([0; 4])),
+        "The target chain ID is not valid"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::{Arc, Mutex},
+    };
+
+    use fungible::{Account, FungibleOperation, FungibleResponse};
+    use linera_sdk::{
+        base::{
+            AccountOwner, Amount, ApplicationId, BlockHeight, BytecodeId, ChainId, ContractAbi,
+            CryptoHash, MessageId, Owner, TimeDelta, Timestamp,
+        },
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime, DataBlobHash,
+    };
+    use non_fungible::{
+        Config, ConfigLimits, FeeShare, Listing, MarketEventKind, Message, Nft, NftStatus,
+        Operation, OutboundTransfer, Parameters, TokenId,
+    };
+    use universal_solver::UniversalSolverAbi;
+
+    use super::{scale_price, NonFungibleTokenContract, NonFungibleTokenState};
+
+    /// A swap requested from the [`MockSolver`].
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Swap {
+        from_token: String,
+        to_token: String,
+        amount: Amount,
+        destination_address: String,
+    }
+
+    /// A stand-in for the universal solver application, recording the swaps it is called
+    /// with and answering each of them with a configured response.
+    #[derive(Clone)]
+    struct MockSolver {
+        response: Vec<u8>,
+        swaps: Arc<Mutex<Vec<Swap>>>,
+    }
+
+    impl MockSolver {
+        fn new(response: <UniversalSolverAbi as ContractAbi>::Response) -> Self {
+            MockSolver {
+                response: bcs::to_bytes(&response).expect("Failed to serialize solver response"),
+                swaps: Arc::default(),
+            }
+        }
+
+        /// Returns the handler answering the calls to the solver `solver_id`.
+        fn handler(
+            &self,
+            solver_id: ApplicationId,
+        ) -> impl FnMut(bool, ApplicationId, Vec<u8>) -> Vec<u8> + Send + 'static {
+            let solver = self.clone();
+            move |_authenticated, application_id, operation| {
+                assert_eq!(
+                    application_id, solver_id,
+                    "Unexpected call to {application_id}"
+                );
+                let operation = bcs::from_bytes::<universal_solver::Operation>(&operation)
+                    .expect("Failed to deserialize solver operation");
+                // The solver may support other operations, but sales only request swaps.
+                #[allow(irrefutable_let_patterns)]
+                let universal_solver::Operation::Swap {
+                    from_token,
+                    to_token,
+                    amount,
+                    destination_address,
+                } = operation
+                else {
+                    panic!("The mock solver only supports swaps");
+                };
+                solver.swaps.lock().unwrap().push(Swap {
+                    from_token,
+                    to_token,
+                    amount: amount
+                        .parse()
+                        .expect("Swapped amount is not a valid amount"),
+                    destination_address,
+                });
+                solver.response.clone()
+            }
+        }
+
+        /// Returns the swaps requested so far, in order.
+        fn swaps(&self) -> Vec<Swap> {
+            self.swaps.lock().unwrap().clone()
+        }
+    }
+
+    /// A stand-in for a fungible token application, holding balances on the current chain and
+    /// rejecting transfers that exceed them.
+    #[derive(Clone, Default)]
+    struct MockFungible {
+        balances: Arc<Mutex<BTreeMap<AccountOwner, Amount>>>,
+    }
+
+    impl MockFungible {
+        fn with_balance(owner: AccountOwner, amount: Amount) -> Self {
+            let token = MockFungible::default();
+            token.balances.lock().unwrap().insert(owner, amount);
+            token
+        }
+
+        /// Returns the handler answering the calls to the token `token_id`.
+        fn handler(
+            &self,
+            token_id: ApplicationId,
+        ) -> impl FnMut(bool, ApplicationId, Vec<u8>) -> Vec<u8> + Send + 'static {
+            let token = self.clone();
+            move |_authenticated, application_id, operation| {
+                assert_eq!(
+                    application_id, token_id,
+                    "Unexpected call to {application_id}"
+                );
+                let operation = bcs::from_bytes::<FungibleOperation>(&operation)
+                    .expect("Failed to deserialize fungible operation");
+                let FungibleOperation::Transfer {
+                    owner,
+                    amount,
+                    target_account,
+                } = operation
+                else {
+                    panic!("The mock fungible token only supports transfers");
+                };
+                let mut balances = token.balances.lock().unwrap();
+                let balance = balances.entry(owner).or_default();
+                *balance = balance
+                    .try_sub(amount)
+                    .expect("Insufficient balance for transfer");
+                let target_balance = balances.entry(target_account.owner).or_default();
+                *target_balance = target_balance.saturating_add(amount);
+                bcs::to_bytes(&FungibleResponse::Ok).expect("Failed to serialize response")
+            }
+        }
+
+        fn balance(&self, owner: AccountOwner) -> Amount {
+            self.balances
+                .lock()
+                .unwrap()
+                .get(&owner)
+                .copied()
+                .unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn transfer_swaps_payment_to_seller() {
+        let solver = MockSolver::new(());
+        let mut contract = create_contract(Config::default(), &solver);
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract
+            .execute_operation(transfer(token_id.clone(), "1"))
+            .blocking_wait();
+
+        assert_eq!(solver.swaps(), [swap(Amount::ONE, "0xseller")]);
+        let nft = contract
+            .state
+            .nfts
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Sold NFT should still be stored");
+        assert_eq!(nft.owner, owner(2));
+        assert_eq!(nft.status, NftStatus::Sold);
+    }
+
+    #[test]
+    fn transfer_pays_the_seller_before_handing_over_the_chain_owner() {
+        for (chain_owner, expected_chain_owner) in
+            [(None, "0xseller"), (Some("0xbuyer"), "0xbuyer")]
+        {
+            let solver = MockSolver::new(());
+            let mut contract = create_contract(Config::default(), &solver);
+            let nft = listed_nft("1");
+            let token_id = nft.token_id.clone();
+            contract.add_nft(nft).blocking_wait();
+
+            let mut operation = transfer(token_id.clone(), "1");
+            if let Operation::Transfer {
+                chain_owner: new_chain_owner,
+                ..
+            } = &mut operation
+            {
+                *new_chain_owner = chain_owner.map(str::to_owned);
+            }
+            contract.execute_operation(operation).blocking_wait();
+
+            assert_eq!(solver.swaps(), [swap(Amount::ONE, "0xseller")]);
+            let nft = contract
+                .state
+                .nfts
+                .get(&token_id)
+                .blocking_wait()
+                .unwrap()
+                .expect("Sold NFT should still be stored");
+            assert_eq!(nft.chain_owner, expected_chain_owner);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "is below the price of")]
+    fn transfer_rejects_underpayment() {
+        let solver = MockSolver::new(());
+        let mut contract = create_contract(Config::default(), &solver);
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract
+            .execute_operation(transfer(token_id, "0.999"))
+            .blocking_wait();
+    }
+
+    #[test]
+    fn transfer_routes_royalty_fee_shares_and_proceeds() {
+        let solver = MockSolver::new(());
+        let mut contract = create_contract(config_with_fee_split(1_000), &solver);
+        let mut nft = listed_nft("1");
+        nft.royalty_bps = 500;
+        nft.chain_minter = "0xminter".to_owned();
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract
+            .execute_operation(transfer(token_id, "1"))
+            .blocking_wait();
+
+        assert_eq!(
+            solver.swaps(),
+            [
+                swap(Amount::from_millis(50), "0xminter"),
+                swap(Amount::from_millis(70), "0xdao"),
+                swap(Amount::from_millis(30), "0xtreasury"),
+                swap(Amount::from_millis(850), "0xseller"),
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_routes_the_whole_amount_of_dust_sales() {
+        let solver = MockSolver::new(());
+        let mut contract = create_contract(config_with_fee_split(1_000), &solver);
+        let price = Amount::from_attos(11);
+        let mut nft = listed_nft(&price.to_string());
+        nft.royalty_bps = 500;
+        nft.chain_minter = "0xminter".to_owned();
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract
+            .execute_operation(transfer(token_id, &price.to_string()))
+            .blocking_wait();
+
+        let swaps = solver.swaps();
+        let routed = swaps.iter().fold(Amount::ZERO, |total, swap| {
+            total.saturating_add(swap.amount)
+        });
+        assert_eq!(routed, price);
+        assert!(swaps.iter().all(|swap| swap.amount > Amount::ZERO));
+    }
+
+    #[test]
+    fn buy_pays_the_listing_in_the_payment_currency() {
+        let token = MockFungible::with_balance(owner(2), Amount::from_tokens(5));
+        let mut contract =
+            create_contract_with_token(config_with_payment_token(), &MockSolver::new(()), &token);
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+        contract
+            .state
+            .listings
+            .insert(&token_id, vec![listing("1", "ETH"), listing("2", "USDC")])
+            .unwrap();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract
+            .execute_operation(buy(token_id.clone()))
+            .blocking_wait();
+
+        assert_eq!(token.balance(owner(2)), Amount::from_tokens(3));
+        assert_eq!(token.balance(owner(1)), Amount::from_tokens(2));
+        let nft = contract
+            .state
+            .nfts
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Bought NFT should still be stored");
+        assert_eq!(nft.owner, owner(2));
+        assert_eq!((nft.price.as_str(), nft.token.as_str()), ("2", "USDC"));
+    }
+
+    #[test]
+    #[should_panic(expected = "NFT is priced in ETH, not in USDC")]
+    fn buy_rejects_nfts_priced_in_another_currency() {
+        let token = MockFungible::with_balance(owner(2), Amount::from_tokens(5));
+        let mut contract =
+            create_contract_with_token(config_with_payment_token(), &MockSolver::new(()), &token);
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
 
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract.execute_operation(buy(token_id)).blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "NFT is not for sale in USDC")]
+    fn buy_respects_accepted_currencies() {
+        let token = MockFungible::with_balance(owner(2), Amount::from_tokens(5));
+        let mut contract =
+            create_contract_with_token(config_with_payment_token(), &MockSolver::new(()), &token);
+        let mut nft = listed_nft("1");
+        nft.token = "USDC".to_owned();
+        nft.accepted_currencies = Some(["ETH".to_owned()].into());
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract.execute_operation(buy(token_id)).blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient balance")]
+    fn buy_rejects_insufficient_funds() {
+        let token = MockFungible::with_balance(owner(2), Amount::from_millis(999));
+        let mut contract =
+            create_contract_with_token(config_with_payment_token(), &MockSolver::new(()), &token);
+        let mut nft = listed_nft("1");
+        nft.token = "USDC".to_owned();
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract.execute_operation(buy(token_id)).blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the minter can give NFTs away")]
+    fn transfer_all_to_owner_respects_secondary_market_settings() {
+        let mut contract =
+            create_contract(config_without_secondary_market(true), &MockSolver::new(()));
+        contract.add_nft(resold_nft()).blocking_wait();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract
+            .execute_operation(Operation::TransferAllToOwner {
+                source_owner: owner(2),
+                target: owner(3),
+            })
+            .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the minter can give NFTs away")]
+    fn merge_owner_respects_secondary_market_settings() {
+        let mut contract =
+            create_contract(config_without_secondary_market(true), &MockSolver::new(()));
+        contract.add_nft(resold_nft()).blocking_wait();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract
+            .execute_operation(Operation::MergeOwner {
+                from: owner(2),
+                to: owner(3),
+            })
+            .blocking_wait();
+    }
+
+    #[test]
+    fn merge_owner_moves_resold_nfts_when_gifts_are_allowed() {
+        let mut contract =
+            create_contract(config_without_secondary_market(false), &MockSolver::new(()));
+        let nft = resold_nft();
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract
+            .execute_operation(Operation::MergeOwner {
+                from: owner(2),
+                to: owner(3),
+            })
+            .blocking_wait();
+
+        let nft = contract
+            .state
+            .nfts
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Merged NFT should still be stored");
+        assert_eq!(nft.owner, owner(3));
+    }
+
+    #[test]
+    fn bounced_sales_are_relisted_when_configured() {
+        let solver = MockSolver::new(());
+        let config = Config {
+            auto_relist_on_bounce: true,
+            listing_fee: Some("0.1".to_owned()),
+            listing_fee_token: "USDC".to_owned(),
+            fee_recipient: "0xfees".to_owned(),
+            ..Config::default()
+        };
+        let mut contract = create_contract(config, &solver);
+
+        let nft = bounce_transfer(&mut contract, true);
+
+        assert_eq!(nft.status, NftStatus::OnSale);
+        assert_eq!(
+            solver.swaps(),
+            [Swap {
+                from_token: "USDC".to_owned(),
+                to_token: "USDC".to_owned(),
+                amount: Amount::from_millis(100),
+                destination_address: "0xfees".to_owned(),
+            }]
+        );
+        let events = contract.state.events.read(..).blocking_wait().unwrap();
+        assert_eq!(
+            events.last().map(|event| event.kind),
+            Some(MarketEventKind::List)
+        );
+        let last_listed_at = contract
+            .state
+            .last_listed_at
+            .get(&nft.token_id)
+            .blocking_wait()
+            .unwrap();
+        assert_eq!(last_listed_at, Some(Timestamp::from(1_000)));
+    }
+
+    #[test]
+    fn bounced_sales_stay_unlisted_by_default() {
+        let solver = MockSolver::new(());
+        let mut contract = create_contract(Config::default(), &solver);
+
+        let nft = bounce_transfer(&mut contract, true);
+
+        assert_eq!(nft.status, NftStatus::NotListed);
+        assert!(solver.swaps().is_empty());
+        assert_eq!(contract.state.events.count(), 0);
+    }
+
+    #[test]
+    fn bounced_gifts_are_never_relisted() {
+        let config = Config {
+            auto_relist_on_bounce: true,
+            ..Config::default()
+        };
+        let mut contract = create_contract(config, &MockSolver::new(()));
+
+        let nft = bounce_transfer(&mut contract, false);
+
+        assert_eq!(nft.status, NftStatus::NotListed);
+    }
+
+    #[test]
+    fn bounced_sales_within_the_relisting_cooldown_stay_unlisted() {
+        let config = Config {
+            auto_relist_on_bounce: true,
+            relist_cooldown_secs: 3_600,
+            ..Config::default()
+        };
+        let mut contract = create_contract(config, &MockSolver::new(()));
+        contract
+            .state
+            .last_listed_at
+            .insert(&listed_nft("1").token_id, Timestamp::from(900))
+            .unwrap();
+
+        let nft = bounce_transfer(&mut contract, true);
+
+        assert_eq!(nft.status, NftStatus::NotListed);
+    }
+
+    #[test]
+    #[should_panic(expected = "NFT must stay listed for at least 3600 seconds")]
+    fn listings_cannot_be_replaced_before_the_minimum_duration() {
+        let config = Config {
+            min_listing_duration_secs: 3_600,
+            ..Config::default()
+        };
+        let mut contract = create_contract(config, &MockSolver::new(()));
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+        contract
+            .state
+            .last_listed_at
+            .insert(&token_id, Timestamp::from(900))
+            .unwrap();
+
+        contract.runtime.set_authenticated_signer(Some(signer(1)));
+        contract
+            .execute_operation(Operation::ListNftForSale {
+                token_id,
+                chain_owner: "0xseller".to_owned(),
+                accepted_currencies: None,
+                listings: Some(vec![listing("2", "ETH")]),
+                expires_at: None,
+            })
+            .blocking_wait();
+    }
+
+    #[test]
+    fn flags_keep_one_report_per_reporter() {
+        let mut contract = create_contract(Config::default(), &MockSolver::new(()));
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        for (seed, reason) in [(2, "spam"), (3, "stolen"), (2, "fake")] {
+            contract
+                .runtime
+                .set_authenticated_signer(Some(signer(seed)));
+            contract
+                .execute_operation(Operation::Flag {
+                    token_id: token_id.clone(),
+                    reason: reason.to_owned(),
+                })
+                .blocking_wait();
+        }
+
+        let flags = contract
+            .state
+            .flags
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .unwrap_or_default();
+        let reports = flags
+            .iter()
+            .map(|flag| (flag.reporter, flag.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(reports, [(owner(3), "stolen"), (owner(2), "fake")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Report reason has 6 characters, more than the maximum of 5")]
+    fn flag_reasons_are_capped() {
+        let config = Config {
+            max_flag_reason_len: 5,
+            ..Config::default()
+        };
+        let mut contract = create_contract(config, &MockSolver::new(()));
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+
+        contract.runtime.set_authenticated_signer(Some(signer(2)));
+        contract
+            .execute_operation(Operation::Flag {
+                token_id,
+                reason: "stolen".to_owned(),
+            })
+            .blocking_wait();
+    }
+
+    #[test]
+    fn collection_royalties_can_reach_the_configured_cap() {
+        let mut contract = create_contract(config_with_royalty_cap(1_000), &MockSolver::new(()));
+
+        contract.runtime.set_authenticated_signer(Some(signer(5)));
+        contract
+            .execute_operation(set_collection_royalty(1_000))
+            .blocking_wait();
+
+        let royalty_bps = contract
+            .state
+            .collection_royalty_bps
+            .get("art")
+            .blocking_wait()
+            .unwrap();
+        assert_eq!(royalty_bps, Some(1_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalty of 1001 basis points exceeds the maximum of 1000")]
+    fn collection_royalties_cannot_exceed_the_configured_cap() {
+        let mut contract = create_contract(config_with_royalty_cap(1_000), &MockSolver::new(()));
+
+        contract.runtime.set_authenticated_signer(Some(signer(5)));
+        contract
+            .execute_operation(set_collection_royalty(1_001))
+            .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "NFT was listed less than 60 seconds ago")]
+    fn relisting_within_the_cooldown_is_rejected() {
+        let mut contract = create_contract(config_with_relist_cooldown(60), &MockSolver::new(()));
+        let token_id = add_listed_nft(&mut contract);
+
+        contract.runtime.set_authenticated_signer(Some(signer(1)));
+        contract
+            .execute_operation(relist(&token_id, "2"))
+            .blocking_wait();
+        contract
+            .execute_operation(relist(&token_id, "3"))
+            .blocking_wait();
+    }
+
+    #[test]
+    fn relisting_after_the_cooldown_succeeds() {
+        let mut contract = create_contract(config_with_relist_cooldown(60), &MockSolver::new(()));
+        let token_id = add_listed_nft(&mut contract);
+
+        contract.runtime.set_authenticated_signer(Some(signer(1)));
+        contract
+            .execute_operation(relist(&token_id, "2"))
+            .blocking_wait();
+        contract
+            .runtime
+            .set_system_time(Timestamp::from(1_000).saturating_add(TimeDelta::from_secs(60)));
+        contract
+            .execute_operation(relist(&token_id, "3"))
+            .blocking_wait();
+
+        let nft = contract
+            .state
+            .nfts
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Relisted NFT should still be stored");
+        assert_eq!(nft.price, "3");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The transfer of this NFT can only be reclaimed 3600 seconds after it was sent"
+    )]
+    fn stuck_transfers_cannot_be_reclaimed_before_the_timeout() {
+        let mut contract =
+            create_contract(config_with_transfer_timeout(3_600), &MockSolver::new(()));
+        let token_id = add_listed_nft(&mut contract);
+        send_to_other_chain(&mut contract, &token_id);
+
+        contract
+            .runtime
+            .set_system_time(Timestamp::from(1_000).saturating_add(TimeDelta::from_secs(3_599)));
+        contract.runtime.set_authenticated_signer(Some(signer(1)));
+        contract
+            .execute_operation(Operation::ReclaimStuckTransfer { token_id })
+            .blocking_wait();
+    }
+
+    #[test]
+    fn stuck_transfers_can_be_reclaimed_after_the_timeout() {
+        let mut contract =
+            create_contract(config_with_transfer_timeout(3_600), &MockSolver::new(()));
+        let token_id = add_listed_nft(&mut contract);
+        send_to_other_chain(&mut contract, &token_id);
+
+        contract
+            .runtime
+            .set_system_time(Timestamp::from(1_000).saturating_add(TimeDelta::from_secs(3_600)));
+        contract.runtime.set_authenticated_signer(Some(signer(1)));
+        contract
+            .execute_operation(Operation::ReclaimStuckTransfer {
+                token_id: token_id.clone(),
+            })
+            .blocking_wait();
+
+        let nft = contract
+            .state
+            .nfts
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Reclaimed NFT should be restored");
+        assert_eq!(nft.owner, owner(1));
+        let is_outbound = contract
+            .state
+            .outbound_transfers
+            .contains_key(&token_id)
+            .blocking_wait()
+            .unwrap();
+        assert!(!is_outbound);
+    }
+
+    #[test]
+    fn reindexing_repairs_corrupted_indexes() {
+        let config = Config {
+            admin: Some(owner(5)),
+            ..Config::default()
+        };
+        let mut contract = create_contract(config, &MockSolver::new(()));
+        let token_id = add_listed_nft(&mut contract);
+        let only_token = BTreeSet::from([token_id.clone()]);
+        contract
+            .state
+            .owned_token_ids
+            .insert(&owner(1), BTreeSet::new())
+            .unwrap();
+        contract
+            .state
+            .owned_token_ids
+            .insert(&owner(3), only_token.clone())
+            .unwrap();
+        contract
+            .state
+            .status_token_ids
+            .remove(&NftStatus::OnSale)
+            .unwrap();
+
+        contract.runtime.set_authenticated_signer(Some(signer(5)));
+        contract
+            .execute_operation(Operation::ReindexAll)
+            .blocking_wait();
+
+        let owned_token_ids = |owner| {
+            contract
+                .state
+                .owned_token_ids
+                .get(&owner)
+                .blocking_wait()
+                .unwrap()
+        };
+        assert_eq!(owned_token_ids(owner(1)), Some(only_token.clone()));
+        assert_eq!(owned_token_ids(owner(3)), None);
+        let on_sale = contract
+            .state
+            .status_token_ids
+            .get(&NftStatus::OnSale)
+            .blocking_wait()
+            .unwrap();
+        assert_eq!(on_sale, Some(only_token));
+    }
+
+    #[test]
+    fn mint_price_is_paid_to_the_recipient() {
+        let token = MockFungible::with_balance(owner(1), Amount::ONE);
+        let mut contract =
+            create_contract_with_token(config_with_mint_price(), &MockSolver::new(()), &token);
+
+        contract.charge_mint_price(owner(1));
+
+        assert_eq!(token.balance(owner(1)), Amount::ZERO);
+        assert_eq!(token.balance(owner(9)), Amount::ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient balance")]
+    fn mint_price_requires_sufficient_funds() {
+        let token = MockFungible::with_balance(owner(1), Amount::from_millis(999));
+        let mut contract =
+            create_contract_with_token(config_with_mint_price(), &MockSolver::new(()), &token);
+
+        contract.charge_mint_price(owner(1));
+    }
+
+    #[test]
+    fn scale_price_rounds_down_and_keeps_invalid_prices() {
+        assert_eq!(
+            scale_price("2".to_owned(), 15_000),
+            Amount::from_tokens(3).to_string()
+        );
+        assert_eq!(
+            scale_price(Amount::from_attos(3).to_string(), 5_000),
+            Amount::from_attos(1).to_string()
+        );
+        assert_eq!(scale_price("free".to_owned(), 5_000), "free");
+    }
+
+    /// Creates a contract on a mock runtime, calling `solver` for swaps.
+    fn create_contract(config: Config, solver: &MockSolver) -> NonFungibleTokenContract {
+        create_contract_with_token(config, solver, &MockFungible::default())
+    }
+
+    /// Creates a contract on a mock runtime, calling `solver` for swaps and `token` as the
+    /// application [`payment_token_id`].
+    fn create_contract_with_token(
+        config: Config,
+        solver: &MockSolver,
+        token: &MockFungible,
+    ) -> NonFungibleTokenContract {
+        let solver_id = application_id(1);
+        let mut call_solver = solver.handler(solver_id);
+        let mut call_token = token.handler(payment_token_id());
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(Parameters {
+                universal_solver_id: solver_id.with_abi::<UniversalSolverAbi>(),
+                limits: ConfigLimits::default(),
+            })
+            .with_application_id(application_id(2).with_abi())
+            .with_chain_id(ChainId::root(0))
+            .with_system_time(Timestamp::from(1_000))
+            .with_call_application_handler(move |authenticated, application_id, operation| {
+                if application_id == solver_id {
+                    call_solver(authenticated, application_id, operation)
+                } else {
+                    call_token(authenticated, application_id, operation)
+                }
+            });
+        let mut contract = NonFungibleTokenContract {
+            state: NonFungibleTokenState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+        contract.instantiate(config).blocking_wait();
+        contract
+    }
+
+    /// Bounces the sale, or the gift if not `is_sale`, of [`listed_nft`] to another chain, and
+    /// returns the NFT as restored on this chain.
+    fn bounce_transfer(contract: &mut NonFungibleTokenContract, is_sale: bool) -> Nft {
+        let mut nft = listed_nft("1");
+        nft.status = NftStatus::Sold;
+        let token_id = nft.token_id.clone();
+        let target_account = Account {
+            chain_id: ChainId::root(1),
+            owner: owner(2),
+        };
+        contract
+            .state
+            .outbound_transfers
+            .insert(
+                &token_id,
+                OutboundTransfer {
+                    nft: nft.clone(),
+                    target_account,
+                    sent_at: Timestamp::from(500),
+                    is_sale,
+                },
+            )
+            .unwrap();
+
+        contract.runtime.set_message_is_bouncing(Some(true));
+        contract
+            .execute_message(Message::Transfer {
+                nft,
+                target_account,
+            })
+            .blocking_wait();
+
+        contract
+            .state
+            .nfts
+            .get(&token_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Bounced NFT should be restored")
+    }
+
+    /// A configuration taking a platform fee of `platform_fee_bps`, split 70/30.
+    fn config_with_fee_split(platform_fee_bps: u16) -> Config {
+        Config {
+            platform_fee_bps,
+            fee_recipients: vec![
+                FeeShare {
+                    recipient: owner(10),
+                    bps: 7_000,
+                    address: "0xdao".to_owned(),
+                },
+                FeeShare {
+                    recipient: owner(11),
+                    bps: 3_000,
+                    address: "0xtreasury".to_owned(),
+                },
+            ],
+            ..Config::default()
+        }
+    }
+
+    /// A configuration administered by `owner(5)`, capping royalties at `max_royalty_bps`.
+    fn config_with_royalty_cap(max_royalty_bps: u16) -> Config {
+        Config {
+            admin: Some(owner(5)),
+            max_royalty_bps,
+            ..Config::default()
+        }
+    }
+
+    fn set_collection_royalty(royalty_bps: u16) -> Operation {
+        Operation::SetCollectionRoyalty {
+            collection: "art".to_owned(),
+            royalty_bps,
+        }
+    }
+
+    /// A configuration requiring `relist_cooldown_secs` between two listings of an NFT.
+    fn config_with_relist_cooldown(relist_cooldown_secs: u64) -> Config {
+        Config {
+            relist_cooldown_secs,
+            ..Config::default()
+        }
+    }
+
+    /// A configuration letting stuck transfers be reclaimed after `transfer_timeout_secs`.
+    fn config_with_transfer_timeout(transfer_timeout_secs: u64) -> Config {
+        Config {
+            transfer_timeout_secs,
+            ..Config::default()
+        }
+    }
+
+    /// A configuration selling NFTs with `Buy` for USDC, in the [`MockFungible`] token.
+    fn config_with_payment_token() -> Config {
+        Config {
+            payment_token: Some(payment_token_id()),
+            payment_currency: "USDC".to_owned(),
+            ..Config::default()
+        }
+    }
+
+    /// A configuration disabling secondary sales, and secondary gifts too if `disable_gifts`.
+    fn config_without_secondary_market(disable_gifts: bool) -> Config {
+        Config {
+            secondary_sales_enabled: false,
+            secondary_gifts_enabled: !disable_gifts,
+            ..Config::default()
+        }
+    }
+
+    /// An unlisted NFT minted by `owner(1)` and now owned by `owner(2)`.
+    fn resold_nft() -> Nft {
+        let mut nft = listed_nft("1");
+        nft.owner = owner(2);
+        nft.status = NftStatus::NotListed;
+        nft
+    }
+
+    /// Stores [`listed_nft`] at a price of 1 ETH, and returns its ID.
+    fn add_listed_nft(contract: &mut NonFungibleTokenContract) -> TokenId {
+        let nft = listed_nft("1");
+        let token_id = nft.token_id.clone();
+        contract.add_nft(nft).blocking_wait();
+        token_id
+    }
+
+    /// Sends the NFT `token_id` of `owner(1)` to `owner(2)` on another chain, which never
+    /// acknowledges it.
+    fn send_to_other_chain(contract: &mut NonFungibleTokenContract, token_id: &TokenId) {
+        contract
+            .execute_operation(Operation::Claim {
+                source_account: Account {
+                    chain_id: ChainId::root(0),
+                    owner: owner(1),
+                },
+                token_id: token_id.clone(),
+                target_account: Account {
+                    chain_id: ChainId::root(1),
+                    owner: owner(2),
+                },
+            })
+            .blocking_wait();
+    }
+
+    /// A configuration charging 1 token of the [`MockFungible`] token per mint, paid to
+    /// `owner(9)`.
+    fn config_with_mint_price() -> Config {
+        Config {
+            payment_token: Some(payment_token_id()),
+            mint_price: Some(Amount::ONE),
+            mint_price_recipient: Some(owner(9)),
+            ..Config::default()
+        }
+    }
+
+    /// An NFT of `owner(1)` on sale for `price` ETH, paid out to `0xseller`.
+    fn listed_nft(price: &str) -> Nft {
+        Nft {
+            token_id: TokenId { id: vec![1] },
+            owner: owner(1),
+            name: "Test NFT".to_owned(),
+            minter: owner(1),
+            blob_hash: DataBlobHash(CryptoHash::from([1; 4])),
+            token: "ETH".to_owned(),
+            price: price.to_owned(),
+            id: 1,
+            chain_minter: String::new(),
+            chain_owner: "0xseller".to_owned(),
+            description: String::new(),
+            collection: String::new(),
+            status: NftStatus::OnSale,
+            accepted_currencies: None,
+            royalty_bps: 0,
+            revealable: false,
+            revealed: false,
+            updated_at: Timestamp::from(0),
+            soulbound: false,
+        }
+    }
+
+    /// A purchase of `token_id` by `owner(2)`, paying `amount` ETH bought with USDC.
+    fn transfer(token_id: TokenId, amount: &str) -> Operation {
+        Operation::Transfer {
+            source_owner: owner(1),
+            token_id,
+            target_account: Account {
+                chain_id: ChainId::root(0),
+                owner: owner(2),
+            },
+            chain_owner: None,
+            buy_from_token: "USDC".to_owned(),
+            to_token: "ETH".to_owned(),
+            amount: amount.to_owned(),
+            memo: None,
+            royalty_bps_override: None,
+        }
+    }
+
+    /// A purchase of `token_id` by `owner(2)` with the payment token.
+    fn buy(token_id: TokenId) -> Operation {
+        Operation::Buy {
+            token_id,
+            payment: Account {
+                chain_id: ChainId::root(0),
+                owner: owner(2),
+            },
+        }
+    }
+
+    /// A relisting of `token_id` by its owner at `price` ETH.
+    fn relist(token_id: &TokenId, price: &str) -> Operation {
+        Operation::Relist {
+            token_id: token_id.clone(),
+            price: price.to_owned(),
+            token: "ETH".to_owned(),
+        }
+    }
+
+    fn listing(price: &str, currency: &str) -> Listing {
+        Listing {
+            price: price.to_owned(),
+            currency: currency.to_owned(),
+        }
+    }
+
+    fn swap(amount: Amount, destination_address: &str) -> Swap {
+        Swap {
+            from_token: "USDC".to_owned(),
+            to_token: "ETH".to_owned(),
+            amount,
+            destination_address: destination_address.to_owned(),
+        }
+    }
+
+    fn owner(seed: u64) -> AccountOwner {
+        AccountOwner::User(signer(seed))
+    }
+
+    fn signer(seed: u64) -> Owner {
+        Owner(CryptoHash::from([seed; 4]))
+    }
+
+    fn payment_token_id() -> ApplicationId {
+        application_id(3)
+    }
+
+    fn application_id(seed: u64) -> ApplicationId {
+        ApplicationId {
+            bytecode_id: BytecodeId::new(CryptoHash::from([seed; 4]), CryptoHash::from([seed; 4])),
+            creation: MessageId {
+                chain_id: ChainId::root(0),
+                height: BlockHeight::ZERO,
+                index: 0,
+            },
+        }
     }
 }
@@ -3,16 +3,25 @@
 
 /*! ABI of the Non-Fungible Token Example Application */
 
-use std::fmt::{Display, Formatter};
-use std::ptr::hash;
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+    ptr::hash,
+};
+
 use async_graphql::{Enum, InputObject, Request, Response, SimpleObject};
 use fungible::Account;
 use linera_sdk::{
-    base::{AccountOwner, ApplicationId, ChainId, ContractAbi, ServiceAbi},
+    base::{
+        crypto::{BcsSignable, PublicKey},
+        AccountOwner, Amount, ApplicationId, ChainId, ContractAbi, Owner, ServiceAbi, Timestamp,
+    },
     graphql::GraphQLMutationRoot,
     DataBlobHash, ToBcsBytes,
 };
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use universal_solver::UniversalSolverAbi;
 
 #[derive(
     Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Ord, PartialOrd, SimpleObject, InputObject,
@@ -22,6 +31,41 @@ pub struct TokenId {
     pub id: Vec<u8>,
 }
 
+/// Version of the GraphQL and operation API, bumped whenever operations or fields change so
+/// that clients can detect capabilities.
+pub const API_VERSION: &str = "0.16.0";
+
+/// Names of the operations this version supports, in the order of [`Operation`]. Keep in sync
+/// when adding operations.
+pub const SUPPORTED_OPERATIONS: &[&str] = &[
+    "Mint",
+    "Transfer",
+    "Buy",
+    "Claim",
+    "BatchClaim",
+    "SafeTransfer",
+    "TransferAllToOwner",
+    "MergeOwner",
+    "SetStatusForOwned",
+    "AdjustPrices",
+    "ReclaimStuckTransfer",
+    "Reveal",
+    "UpdateExternalId",
+    "SetChainOwner",
+    "SetMinter",
+    "SetCollectionRoyalty",
+    "SetCollectionMeta",
+    "ReindexAll",
+    "Burn",
+    "SetListingDeadline",
+    "CancelListing",
+    "Relist",
+    "Flag",
+    "Moderate",
+    "RedeemVoucher",
+    "ListNftForSale",
+];
+
 pub struct NonFungibleTokenAbi;
 
 impl ContractAbi for NonFungibleTokenAbi {
@@ -34,6 +78,462 @@ impl ServiceAbi for NonFungibleTokenAbi {
     type QueryResponse = Response;
 }
 
+/// The application parameters, fixed when the application is created.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Parameters {
+    /// The universal solver application routing swaps for sales and listing fees.
+    pub universal_solver_id: ApplicationId<UniversalSolverAbi>,
+    /// Bounds the configuration must stay within.
+    #[serde(default)]
+    pub limits: ConfigLimits,
+}
+
+/// Bounds on the configuration, set by the application creator in the [`Parameters`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigLimits {
+    /// Highest platform fee, in basis points, the configuration may charge.
+    pub max_platform_fee_bps: u16,
+    /// Highest royalty cap, in basis points, the configuration may allow.
+    pub max_royalty_bps: u16,
+}
+
+impl Default for ConfigLimits {
+    fn default() -> Self {
+        ConfigLimits {
+            max_platform_fee_bps: 10_000,
+            max_royalty_bps: 10_000,
+        }
+    }
+}
+
+/// The application configuration, provided as the instantiation argument.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+#[serde(default)]
+pub struct Config {
+    /// The account allowed to perform administrative operations, if any.
+    pub admin: Option<AccountOwner>,
+    /// Maximum number of characters in an NFT name.
+    pub max_name_len: u32,
+    /// Maximum number of characters in an NFT description.
+    pub max_description_len: u32,
+    /// Minimum number of seconds between two listings of the same NFT.
+    pub relist_cooldown_secs: u64,
+    /// Whether sales must pay the creator royalty.
+    pub royalty_mode: RoyaltyMode,
+    /// Number of seconds after which an unacknowledged cross-chain transfer can be reclaimed.
+    pub transfer_timeout_secs: u64,
+    /// Fee charged for listing an NFT with `ListNftForSale`, in `listing_fee_token`, if any.
+    pub listing_fee: Option<String>,
+    /// Currency the listing fee is paid in.
+    pub listing_fee_token: String,
+    /// External address that receives the listing fees.
+    pub fee_recipient: String,
+    /// Maximum creator royalty an NFT can be minted with, in basis points.
+    pub max_royalty_bps: u16,
+    /// Formats that `chain_minter` and `chain_owner` addresses must follow, by `token` symbol.
+    /// Tokens without a rule are not validated.
+    pub address_formats: Vec<AddressRule>,
+    /// Whether the administrator may reassign the minter of an NFT with `SetMinter`.
+    pub minter_reassignable: bool,
+    /// Time from which anyone can mint, if minting is time-gated.
+    pub mint_start_at: Option<Timestamp>,
+    /// Time from which allowlisted minters can mint, if earlier than `mint_start_at`.
+    pub allowlist_mint_start_at: Option<Timestamp>,
+    /// Minters allowed to mint from `allowlist_mint_start_at`.
+    pub allowlist: BTreeSet<AccountOwner>,
+    /// Who may burn an NFT.
+    pub burn_policy: BurnPolicy,
+    /// The fungible token application used to pay for NFTs with `Buy`, if enabled.
+    pub payment_token: Option<ApplicationId>,
+    /// Currency symbol of the `payment_token`. `Buy` only sells NFTs priced, or offering a
+    /// listing, in this currency.
+    pub payment_currency: String,
+    /// Whether NFTs can be resold by owners other than their minter.
+    pub secondary_sales_enabled: bool,
+    /// Whether NFTs can be given away by owners other than their minter when secondary sales
+    /// are disabled.
+    pub secondary_gifts_enabled: bool,
+    /// Minimum number of seconds an NFT stays on sale before its listing can be cancelled or
+    /// replaced.
+    pub min_listing_duration_secs: u64,
+    /// Platform fee taken from sales, in basis points of the price. `Buy` purchases pay it to
+    /// the `fee_recipients`' accounts, and `Transfer` sales route it to their external
+    /// addresses via the solver.
+    pub platform_fee_bps: u16,
+    /// How the platform fee is split, in basis points of the fee summing up to 10000.
+    pub fee_recipients: Vec<FeeShare>,
+    /// Maximum number of characters in a transfer memo.
+    pub max_memo_len: u32,
+    /// Maximum number of characters in the reason of a moderation report.
+    pub max_flag_reason_len: u32,
+    /// Maximum number of tokens a batch operation can handle, to stay within block limits.
+    pub max_batch_size: u32,
+    /// Price of minting an NFT, paid by the minter in `payment_token`, if minting isn't free.
+    pub mint_price: Option<Amount>,
+    /// Account receiving the mint payments.
+    pub mint_price_recipient: Option<AccountOwner>,
+    /// Whether an NFT whose cross-chain sale bounces goes back on sale at its previous price,
+    /// paying the listing fee again, instead of staying with its owner unlisted. Bounced gifts,
+    /// and sales still in their relisting cooldown, always stay unlisted.
+    pub auto_relist_on_bounce: bool,
+}
+
+/// A recipient's share of the platform fee.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct FeeShare {
+    pub recipient: AccountOwner,
+    pub bps: u16,
+    #[serde(default)]
+    pub address: String, // external address receiving the share on `Transfer` sales
+}
+
+/// Who is allowed to burn an NFT.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum BurnPolicy {
+    /// Only the owner can burn an NFT.
+    #[default]
+    OwnerOnly,
+    /// The owner or the minter can burn an NFT, e.g. for redemptions.
+    OwnerOrMinter,
+}
+
+/// Requires the external addresses of NFTs priced in `token` to follow `format`.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct AddressRule {
+    pub token: String,
+    pub format: AddressFormat,
+}
+
+/// A format of external chain addresses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum AddressFormat {
+    /// `0x` followed by 40 hexadecimal digits, as on Ethereum.
+    Evm,
+    /// 32 to 44 base58 characters, as on Solana.
+    Base58,
+}
+
+impl AddressFormat {
+    /// Returns whether `address` is well-formed.
+    pub fn is_valid(&self, address: &str) -> bool {
+        match self {
+            AddressFormat::Evm => address.strip_prefix("0x").is_some_and(|digits| {
+                digits.len() == 40 && digits.chars().all(|c| c.is_ascii_hexdigit())
+            }),
+            AddressFormat::Base58 => {
+                (32..=44).contains(&address.len())
+                    && address
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c))
+            }
+        }
+    }
+}
+
+/// How creator royalties are handled on sales.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum RoyaltyMode {
+    /// Sales that can't route the royalty to the creator are rejected.
+    Enforced,
+    /// Royalties are paid when possible, and skipped otherwise.
+    #[default]
+    Optional,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            admin: None,
+            max_name_len: 128,
+            max_description_len: 2048,
+            relist_cooldown_secs: 0,
+            royalty_mode: RoyaltyMode::default(),
+            transfer_timeout_secs: 86_400,
+            listing_fee: None,
+            listing_fee_token: String::new(),
+            fee_recipient: String::new(),
+            max_royalty_bps: 10_000,
+            address_formats: Vec::new(),
+            minter_reassignable: false,
+            mint_start_at: None,
+            allowlist_mint_start_at: None,
+            allowlist: BTreeSet::new(),
+            burn_policy: BurnPolicy::default(),
+            payment_token: None,
+            payment_currency: String::new(),
+            secondary_sales_enabled: true,
+            secondary_gifts_enabled: true,
+            min_listing_duration_secs: 0,
+            platform_fee_bps: 0,
+            fee_recipients: Vec::new(),
+            max_memo_len: 280,
+            max_flag_reason_len: 280,
+            max_batch_size: 50,
+            mint_price: None,
+            mint_price_recipient: None,
+            auto_relist_on_bounce: false,
+        }
+    }
+}
+
+impl Config {
+    /// Checks that an NFT's name and description fit within the configured limits.
+    pub fn check_metadata(
+        &self,
+        name: &str,
+        description: &str,
+    ) -> Result<(), NonFungibleTokenError> {
+        let name_len = name.chars().count();
+        if name_len > self.max_name_len as usize {
+            return Err(NonFungibleTokenError::NameTooLong {
+                len: name_len,
+                max: self.max_name_len,
+            });
+        }
+        let description_len = description.chars().count();
+        if description_len > self.max_description_len as usize {
+            return Err(NonFungibleTokenError::DescriptionTooLong {
+                len: description_len,
+                max: self.max_description_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the given external addresses of an NFT priced in `token` follow the format
+    /// configured for that token, if any. Empty addresses are not checked.
+    pub fn check_addresses(
+        &self,
+        token: &str,
+        addresses: &[&str],
+    ) -> Result<(), NonFungibleTokenError> {
+        let Some(rule) = self.address_formats.iter().find(|rule| rule.token == token) else {
+            return Ok(());
+        };
+        for address in addresses {
+            if !address.is_empty() && !rule.format.is_valid(address) {
+                return Err(NonFungibleTokenError::InvalidAddress {
+                    address: address.to_string(),
+                    token: token.to_owned(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `minter` may mint at time `now`, given the public and allowlist mint
+    /// windows.
+    pub fn check_mint_window(
+        &self,
+        minter: &AccountOwner,
+        now: Timestamp,
+    ) -> Result<(), NonFungibleTokenError> {
+        let Some(mint_start_at) = self.mint_start_at else {
+            return Ok(());
+        };
+        let start = match self.allowlist_mint_start_at {
+            Some(allowlist_mint_start_at) if self.allowlist.contains(minter) => {
+                allowlist_mint_start_at.min(mint_start_at)
+            }
+            _ => mint_start_at,
+        };
+        if now < start {
+            return Err(NonFungibleTokenError::MintNotStarted { start });
+        }
+        Ok(())
+    }
+
+    /// Checks that `nft` may change hands, in a sale if `is_sale` is set, given whether minted
+    /// NFTs can be resold or given away.
+    pub fn check_secondary_market(
+        &self,
+        nft: &Nft,
+        is_sale: bool,
+    ) -> Result<(), NonFungibleTokenError> {
+        if nft.owner == nft.minter || self.secondary_sales_enabled {
+            return Ok(());
+        }
+        if is_sale {
+            return Err(NonFungibleTokenError::SecondarySalesDisabled);
+        }
+        if !self.secondary_gifts_enabled {
+            return Err(NonFungibleTokenError::SecondaryGiftsDisabled);
+        }
+        Ok(())
+    }
+
+    /// Checks that the platform fee is at most 100% and that, if there is one, the fee
+    /// recipients' shares add up to exactly 10000 basis points.
+    pub fn check_fee_recipients(&self) -> Result<(), NonFungibleTokenError> {
+        if self.platform_fee_bps > 10_000 {
+            return Err(NonFungibleTokenError::PlatformFeeTooHigh(
+                self.platform_fee_bps,
+            ));
+        }
+        let total: u32 = self
+            .fee_recipients
+            .iter()
+            .map(|share| u32::from(share.bps))
+            .sum();
+        if (self.platform_fee_bps > 0 || !self.fee_recipients.is_empty()) && total != 10_000 {
+            return Err(NonFungibleTokenError::InvalidFeeSplit { total });
+        }
+        Ok(())
+    }
+
+    /// Checks that the configuration stays within the `limits` of the application parameters,
+    /// and that its fee split is consistent.
+    pub fn check_limits(&self, limits: &ConfigLimits) -> Result<(), NonFungibleTokenError> {
+        if self.platform_fee_bps > limits.max_platform_fee_bps {
+            return Err(NonFungibleTokenError::PlatformFeeAboveLimit {
+                bps: self.platform_fee_bps,
+                max: limits.max_platform_fee_bps,
+            });
+        }
+        if self.max_royalty_bps > limits.max_royalty_bps {
+            return Err(NonFungibleTokenError::RoyaltyCapAboveLimit {
+                bps: self.max_royalty_bps,
+                max: limits.max_royalty_bps,
+            });
+        }
+        self.check_fee_recipients()
+    }
+
+    /// Returns the share of a platform `fee` owed to each recipient. Rounding dust goes to the
+    /// last recipient, so that the shares add up to the whole fee.
+    pub fn platform_fee_shares(&self, fee: Amount) -> Vec<(FeeShare, Amount)> {
+        let mut remaining = fee;
+        let mut shares = Vec::with_capacity(self.fee_recipients.len());
+        for (index, share) in self.fee_recipients.iter().enumerate() {
+            let amount = if index + 1 == self.fee_recipients.len() {
+                remaining
+            } else {
+                basis_points_of(fee, share.bps)
+            };
+            remaining = remaining.saturating_sub(amount);
+            shares.push((share.clone(), amount));
+        }
+        shares
+    }
+
+    /// Checks that a batch of `len` tokens fits within the configured limit.
+    pub fn check_batch_size(&self, len: usize) -> Result<(), NonFungibleTokenError> {
+        if len > self.max_batch_size as usize {
+            return Err(NonFungibleTokenError::BatchTooLarge {
+                len,
+                max: self.max_batch_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that a transfer memo fits within the configured limit.
+    pub fn check_memo(&self, memo: &str) -> Result<(), NonFungibleTokenError> {
+        let len = memo.chars().count();
+        if len > self.max_memo_len as usize {
+            return Err(NonFungibleTokenError::MemoTooLong {
+                len,
+                max: self.max_memo_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the reason of a moderation report fits within the configured limit.
+    pub fn check_flag_reason(&self, reason: &str) -> Result<(), NonFungibleTokenError> {
+        let len = reason.chars().count();
+        if len > self.max_flag_reason_len as usize {
+            return Err(NonFungibleTokenError::FlagReasonTooLong {
+                len,
+                max: self.max_flag_reason_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that a creator royalty doesn't exceed the configured cap.
+    pub fn check_royalty(&self, royalty_bps: u16) -> Result<(), NonFungibleTokenError> {
+        if royalty_bps > self.max_royalty_bps {
+            return Err(NonFungibleTokenError::RoyaltyTooHigh {
+                bps: royalty_bps,
+                max: self.max_royalty_bps,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the royalty of a single sale may be overridden with `royalty_bps`, which is
+    /// only possible in [`RoyaltyMode::Optional`] mode.
+    pub fn check_royalty_override(&self, royalty_bps: u16) -> Result<(), NonFungibleTokenError> {
+        if self.royalty_mode == RoyaltyMode::Enforced {
+            return Err(NonFungibleTokenError::RoyaltyOverrideNotAllowed);
+        }
+        self.check_royalty(royalty_bps)
+    }
+}
+
+/// An error rejecting an operation.
+#[derive(Debug, Error)]
+pub enum NonFungibleTokenError {
+    #[error("NFT name has {len} characters, more than the maximum of {max}")]
+    NameTooLong { len: usize, max: u32 },
+
+    #[error("NFT description has {len} characters, more than the maximum of {max}")]
+    DescriptionTooLong { len: usize, max: u32 },
+
+    #[error("Royalty of {bps} basis points exceeds the maximum of {max}")]
+    RoyaltyTooHigh { bps: u16, max: u16 },
+
+    #[error("Royalties can't be overridden when they are enforced")]
+    RoyaltyOverrideNotAllowed,
+
+    #[error("Batch of {len} tokens exceeds the maximum of {max}")]
+    BatchTooLarge { len: usize, max: u32 },
+
+    #[error("Memo has {len} characters, more than the maximum of {max}")]
+    MemoTooLong { len: usize, max: u32 },
+
+    #[error("Report reason has {len} characters, more than the maximum of {max}")]
+    FlagReasonTooLong { len: usize, max: u32 },
+
+    #[error("Platform fee of {0} basis points exceeds 10000")]
+    PlatformFeeTooHigh(u16),
+
+    #[error("Platform fee of {bps} basis points exceeds the limit of {max}")]
+    PlatformFeeAboveLimit { bps: u16, max: u16 },
+
+    #[error("Royalty cap of {bps} basis points exceeds the limit of {max}")]
+    RoyaltyCapAboveLimit { bps: u16, max: u16 },
+
+    #[error("Fee recipient shares add up to {total} basis points instead of 10000")]
+    InvalidFeeSplit { total: u32 },
+
+    #[error("Only primary sales from the minter are allowed")]
+    SecondarySalesDisabled,
+
+    #[error("Only the minter can give NFTs away")]
+    SecondaryGiftsDisabled,
+
+    #[error("NFT is soulbound and can't be transferred")]
+    Soulbound,
+
+    #[error("Minting is only open from {start}")]
+    MintNotStarted { start: Timestamp },
+
+    #[error("{address:?} is not a valid {token} address")]
+    InvalidAddress { address: String, token: String },
+
+    #[error("NFT is priced in {expected}, not in {currency}")]
+    WrongCurrency { expected: String, currency: String },
+
+    #[error("Invalid payment amount {0:?}")]
+    InvalidAmount(String),
+
+    #[error("Payment of {amount} is below the price of {price}")]
+    Underpaid { amount: Amount, price: Amount },
+}
+
 /// An operation.
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
@@ -42,23 +542,34 @@ pub enum Operation {
         minter: AccountOwner,
         name: String,
         blob_hash: DataBlobHash,
-        token: String, // ETH, SOL
-        price: String, // 0.05 [token]
-        id: u64, // specific chain nft id
+        token: String,        // ETH, SOL
+        price: String,        // 0.05 [token]
+        id: u64,              // specific chain nft id
         chain_minter: String, // chain nft minter
-        chain_owner: String, // chain nft owner
+        chain_owner: String,  // chain nft owner
         description: String,
+        collection: String,
+        royalty_bps: Option<u16>, // creator royalty in basis points, or the collection default
+        revealable: bool,         // whether the blob and metadata can be replaced by `Reveal`
+        list_immediately: bool,   // whether the NFT is put on sale at mint or left unlisted
+        soulbound: bool, // whether the NFT is bound to its first owner and can't be transferred
     },
     /// Transfers a token from a (locally owned) account to a (possibly remote) account.
     Transfer {
         source_owner: AccountOwner,
         token_id: TokenId,
         target_account: Account,
-        chain_owner: String,
+        chain_owner: Option<String>, // new chain nft owner, kept unchanged if `None` or empty
         buy_from_token: String,
         to_token: String,
         amount: String,
+        memo: Option<String>, // note recorded in the sale event, not stored on the NFT
+        royalty_bps_override: Option<u16>, // royalty for this sale only, agreed by the minter
     },
+    /// Buys a token on sale by paying its price from the `payment` account directly in the
+    /// configured fungible payment token, without a swap. The token must be priced, or offer a
+    /// listing, in the payment currency. The token goes to the `payment` account.
+    Buy { token_id: TokenId, payment: Account },
     /// Same as `Transfer` but the source account may be remote. Depending on its
     /// configuration, the target chain may take time or refuse to process
     /// the message.
@@ -67,10 +578,134 @@ pub enum Operation {
         token_id: TokenId,
         target_account: Account,
     },
+    /// Same as `Claim` for several tokens of the same source account, sending a single
+    /// message if the source account is remote.
+    BatchClaim {
+        source_account: Account,
+        token_ids: Vec<TokenId>,
+        target_account: Account,
+    },
+    /// Same as `Transfer` without a swap, but if the target owner is an application, it
+    /// must accept the token through the [`NftReceiverAbi`] hook, or the transfer is rejected.
+    SafeTransfer {
+        source_owner: AccountOwner,
+        token_id: TokenId,
+        target_account: Account,
+        memo: Option<String>, // note recorded in the gift event, not stored on the NFT
+    },
+    /// Reassigns every token of `source_owner` to `target` on the current chain. Tokens that
+    /// have been sold cannot be moved this way, and the whole operation fails if any of them is,
+    /// or if any of them couldn't be given away under the secondary market settings.
+    TransferAllToOwner {
+        source_owner: AccountOwner,
+        target: AccountOwner,
+    },
+    /// Moves all tokens of `from` to `to` on the current chain, whatever their status, e.g.
+    /// when rotating keys. Fails if any of them is soulbound, or couldn't be given away under
+    /// the secondary market settings, since the contract can't tell a key rotation from a gift.
+    /// Authenticated by `from`.
+    MergeOwner {
+        from: AccountOwner,
+        to: AccountOwner,
+    },
+    /// Lists or delists all tokens of an owner at once. Sold tokens are left untouched, since
+    /// they need a new price to be relisted.
+    SetStatusForOwned {
+        source_owner: AccountOwner,
+        status: NftStatus,
+    },
+    /// Multiplies the prices of all of an owner's tokens on sale by `factor_bps / 10000`.
+    AdjustPrices {
+        source_owner: AccountOwner,
+        factor_bps: u32,
+    },
+    /// Restores a token whose cross-chain transfer was neither received nor bounced within
+    /// the configured timeout. Authenticated by the token's owner before the transfer.
+    ReclaimStuckTransfer { token_id: TokenId },
+    /// Replaces the blob, name and description of a revealable token, keeping its ID and
+    /// ownership. A token can only be revealed once. Authenticated by the token's minter or the
+    /// administrator.
+    Reveal {
+        token_id: TokenId,
+        new_blob_hash: DataBlobHash,
+        new_name: String,
+        new_description: String,
+    },
+    /// Changes the external chain ID of a token, moving its `nftUsingBlobHash` mapping to
+    /// the new ID. Authenticated by the token's owner or the administrator.
+    UpdateExternalId { token_id: TokenId, new_id: u64 },
+    /// Changes the external chain owner of a token without transferring it, e.g. after an
+    /// external wallet rotation. Authenticated by the token's owner.
+    SetChainOwner {
+        token_id: TokenId,
+        chain_owner: String,
+    },
+    /// Hands the creator credit of a token over to a new minter. Only allowed if the
+    /// configuration makes minters reassignable, and authenticated by the administrator.
+    SetMinter {
+        token_id: TokenId,
+        new_minter: AccountOwner,
+    },
+    /// Sets the royalty applied to new tokens of a collection that are minted without an
+    /// explicit royalty. Authenticated by the administrator.
+    SetCollectionRoyalty {
+        collection: String,
+        royalty_bps: u16,
+    },
+    /// Sets the display metadata of a collection. It is first set by the administrator or a
+    /// minter of one of the collection's tokens, who becomes the collection's creator. Later
+    /// updates are authenticated by the creator or the administrator.
+    SetCollectionMeta {
+        collection: String,
+        name: String,
+        description: String,
+        banner_blob_hash: Option<DataBlobHash>,
+    },
+    /// Clears and rebuilds every secondary index from the stored tokens. Authenticated by the
+    /// administrator.
+    ReindexAll,
+    /// Burns a token, removing it permanently.
+    Burn { token_id: TokenId },
+    /// Makes the listing of a token on sale expire at the given time. Authenticated by the
+    /// token's owner.
+    SetListingDeadline {
+        token_id: TokenId,
+        expires_at: Timestamp,
+    },
+    /// Takes a token off sale. Authenticated by the token's owner.
+    CancelListing { token_id: TokenId },
+    /// Puts a token back on sale at a new price, e.g. after it was bought.
+    Relist {
+        token_id: TokenId,
+        price: String, // 0.05 [token]
+        token: String, // ETH, SOL
+    },
+    /// Reports a token for moderation. Authenticated by the reporter. A new report by the same
+    /// reporter replaces their previous one.
+    Flag { token_id: TokenId, reason: String },
+    /// Acts on the reports of a token. Authenticated by the administrator.
+    Moderate {
+        token_id: TokenId,
+        action: ModerationAction,
+    },
+    /// Mints a token described by a voucher the minter signed off-chain, to the account
+    /// authenticating the operation. `signature` is the BCS-encoded signature of the voucher
+    /// by `voucher.minter_public_key`. Each voucher can only be redeemed once, on the chain and
+    /// in the application it names.
+    RedeemVoucher {
+        voucher: MintVoucher,
+        signature: Vec<u8>,
+    },
+    /// Puts a token on sale, optionally restricting the currencies a buyer may pay in,
+    /// optionally offering several prices in different currencies, and optionally until a
+    /// given time only. Authenticated by the token's owner.
     ListNftForSale {
         token_id: TokenId,
         chain_owner: String,
-    }
+        accepted_currencies: Option<BTreeSet<String>>,
+        listings: Option<Vec<Listing>>,
+        expires_at: Option<Timestamp>,
+    },
 }
 
 /// A message.
@@ -86,14 +721,138 @@ pub enum Message {
         token_id: TokenId,
         target_account: Account,
     },
+
+    /// Claims several tokens from the given account and starts their transfers to the target
+    /// account.
+    BatchClaim {
+        source_account: Account,
+        token_ids: Vec<TokenId>,
+        target_account: Account,
+    },
+
+    /// Acknowledges to the sending chain that a `Transfer` was received.
+    TransferReceived { token_id: TokenId },
+}
+
+/// One of the prices at which a listed NFT can be bought.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, InputObject, PartialEq, Eq)]
+#[graphql(input_name = "ListingInput")]
+pub struct Listing {
+    pub price: String,    // 0.05 [currency]
+    pub currency: String, // ETH, SOL
+}
+
+/// A cross-chain transfer sent from this chain and not yet acknowledged by the target chain.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct OutboundTransfer {
+    pub nft: Nft,
+    pub target_account: Account,
+    pub sent_at: Timestamp,
+    pub is_sale: bool,
 }
 
+/// Whether an NFT is currently moving between chains, from the point of view of one chain.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum TransferStatus {
+    /// No cross-chain transfer of the NFT is pending.
+    Idle,
+    /// The NFT was sent to another chain, which hasn't acknowledged it yet.
+    Outbound,
+    /// The NFT was claimed from another chain, and hasn't arrived yet.
+    Inbound,
+}
+
+/// The total price of the completed sales an account took part in, across currencies.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct AccountVolume {
+    pub account: AccountOwner,
+    pub volume: Amount,
+}
+
+/// Key of the per-collection sales volume: a collection together with a sale currency.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Ord, PartialOrd, SimpleObject, InputObject,
+)]
+#[graphql(input_name = "CollectionCurrencyInput")]
+pub struct CollectionCurrency {
+    pub collection: String,
+    pub currency: String,
+}
+
+/// Statistics of the completed sales in one currency.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct PriceStats {
+    pub min_sale: Amount,
+    pub max_sale: Amount,
+    pub last_sale: Amount,
+    pub count: u64,
+}
+
+impl PriceStats {
+    /// Returns the statistics of a single sale at `price`.
+    pub fn new(price: Amount) -> Self {
+        PriceStats {
+            min_sale: price,
+            max_sale: price,
+            last_sale: price,
+            count: 1,
+        }
+    }
+
+    /// Adds a sale at `price` to the statistics.
+    pub fn record(&mut self, price: Amount) {
+        self.min_sale = self.min_sale.min(price);
+        self.max_sale = self.max_sale.max(price);
+        self.last_sale = price;
+        self.count += 1;
+    }
+}
+
+/// The ABI that an application must implement to receive NFTs through
+/// [`Operation::SafeTransfer`].
+///
+/// Before finalizing the transfer, the NFT application calls the receiving application with
+/// [`NftReceiverOperation::OnNftReceived`]. The transfer only proceeds if the receiver returns
+/// `true`; applications that don't implement the hook fail the call, which rejects the transfer.
+pub struct NftReceiverAbi;
+
+impl ContractAbi for NftReceiverAbi {
+    type Operation = NftReceiverOperation;
+    type Response = bool;
+}
+
+/// An operation handled by applications able to receive NFTs.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum NftReceiverOperation {
+    /// Notifies the receiver that `token_id` is being transferred to it from `from`.
+    OnNftReceived {
+        token_id: TokenId,
+        from: AccountOwner,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Enum)]
 pub enum NftStatus {
     /// sold status
     Sold,
     /// on sale status
     OnSale,
+    /// minted but never listed for sale
+    NotListed,
+    /// taken down by moderation, and excluded from public queries
+    Hidden,
+}
+
+impl Display for NftStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            NftStatus::Sold => "SOLD",
+            NftStatus::OnSale => "ON_SALE",
+            NftStatus::NotListed => "NOT_LISTED",
+            NftStatus::Hidden => "HIDDEN",
+        };
+        write!(f, "{name}")
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
@@ -104,13 +863,20 @@ pub struct Nft {
     pub name: String,
     pub minter: AccountOwner,
     pub blob_hash: DataBlobHash,
-    pub token: String, // ETH, SOL
-    pub price: String, // 0.05 [token]
-    pub id: u64, // specific chain nft id
+    pub token: String,        // ETH, SOL
+    pub price: String,        // 0.05 [token]
+    pub id: u64,              // specific chain nft id
     pub chain_minter: String, // chain nft minter
-    pub chain_owner: String, // chain nft owner
+    pub chain_owner: String,  // chain nft owner
     pub description: String,
+    pub collection: String,
     pub status: NftStatus,
+    pub accepted_currencies: Option<BTreeSet<String>>, // currencies accepted for a sale, if restricted
+    pub royalty_bps: u16,                              // creator royalty on sales, in basis points
+    pub revealable: bool, // whether the blob and metadata can be replaced by `Reveal`
+    pub revealed: bool,   // whether `Reveal` has replaced the placeholder blob
+    pub updated_at: Timestamp, // last time the NFT was minted, transferred, listed or updated
+    pub soulbound: bool,  // whether the NFT is bound to its owner and can't be transferred
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
@@ -121,14 +887,174 @@ pub struct NftOutput {
     pub name: String,
     pub minter: AccountOwner,
     pub payload: Vec<u8>,
-    pub token: String, // ETH, SOL
-    pub price: String, // 0.05 [token]
-    pub id: u64, // specific chain nft id
+    pub token: String,        // ETH, SOL
+    pub price: String,        // 0.05 [token]
+    pub id: u64,              // specific chain nft id
     pub chain_minter: String, // chain nft minter
-    pub chain_owner: String, // chain nft owner
+    pub chain_owner: String,  // chain nft owner
     pub description: String,
+    pub collection: String,
     pub blob_hash: DataBlobHash,
     pub status: NftStatus,
+    pub accepted_currencies: Option<BTreeSet<String>>,
+    pub royalty_bps: u16,
+    pub revealable: bool,
+    pub revealed: bool,
+    pub updated_at: Timestamp,
+    pub soulbound: bool,
+}
+
+/// The parameters of a mint, signed by the minter so that the token is only minted on chain
+/// when someone redeems the voucher with [`Operation::RedeemVoucher`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, InputObject)]
+pub struct MintVoucher {
+    pub chain_id: ChainId,             // the chain the voucher can be redeemed on
+    pub application_id: ApplicationId, // the NFT application the voucher can be redeemed in
+    pub minter_public_key: PublicKey,
+    pub nonce: u64, // distinguishes the vouchers of a minter, each can be redeemed once
+    pub name: String,
+    pub blob_hash: DataBlobHash,
+    pub token: String,        // ETH, SOL
+    pub price: String,        // 0.05 [token]
+    pub id: u64,              // specific chain nft id
+    pub chain_minter: String, // chain nft minter
+    pub chain_owner: String,  // chain nft owner
+    pub description: String,
+    pub collection: String,
+    pub royalty_bps: Option<u16>, // creator royalty in basis points, or the collection default
+    pub revealable: bool,
+    pub soulbound: bool,
+}
+
+impl BcsSignable for MintVoucher {}
+
+impl MintVoucher {
+    /// Returns the account of the minter who signed the voucher.
+    pub fn minter(&self) -> AccountOwner {
+        AccountOwner::User(Owner::from(self.minter_public_key))
+    }
+}
+
+/// Display metadata of a collection.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct CollectionMeta {
+    pub name: String,
+    pub description: String,
+    pub banner_blob_hash: Option<DataBlobHash>,
+    /// The account that first set the metadata, allowed to update it.
+    pub creator: AccountOwner,
+}
+
+/// A report of a token for moderation.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct Flag {
+    pub reporter: AccountOwner,
+    pub reason: String,
+    pub flagged_at: Timestamp,
+}
+
+/// What the administrator does about the reports of a token.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum ModerationAction {
+    /// Takes the token down, hiding it from public queries. Its owner can't list it again.
+    Hide,
+    /// Brings a hidden token back, unlisted.
+    Unhide,
+    /// Dismisses the reports of the token.
+    ClearFlags,
+}
+
+/// The kind of a [`MarketEvent`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum MarketEventKind {
+    /// An NFT was minted.
+    Mint,
+    /// An NFT was put on sale.
+    List,
+    /// An NFT was bought.
+    Sale,
+    /// An NFT was burned.
+    Burn,
+    /// An NFT was given away with `SafeTransfer`.
+    Gift,
+}
+
+/// An entry of the marketplace event log, which indexers can tail using `sequence` as a cursor.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct MarketEvent {
+    /// Position of the event in the log, starting at 1.
+    pub sequence: u64,
+    pub kind: MarketEventKind,
+    pub token_id: TokenId,
+    pub timestamp: Timestamp,
+    /// Note attached by the sender of a sale or gift, if any.
+    pub memo: Option<String>,
+}
+
+/// Supply counters of the NFTs handled by this chain, used to reconcile minting and burning.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct SupplyStats {
+    /// Number of NFTs ever minted on this chain.
+    pub ever_minted: u64,
+    /// Number of NFTs currently stored on this chain.
+    pub currently_active: u64,
+    /// Number of NFTs burned on this chain.
+    pub burned: u64,
+}
+
+/// A page of raw NFT records, as returned by the `exportNfts` query.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject)]
+pub struct ExportPage {
+    /// The NFTs of this page, in token ID order.
+    pub items: Vec<Nft>,
+    /// Cursor to pass as `after` to fetch the next page, or `None` if this is the last one.
+    pub next: Option<String>,
+}
+
+/// Aggregate figures of a collection, with prices in a single currency.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct CollectionStats {
+    /// Number of NFTs of the collection stored on this chain.
+    pub item_count: u64,
+    /// Number of distinct owners of those NFTs.
+    pub owner_count: u64,
+    /// Lowest price among the NFTs of the collection on sale in the currency, if any.
+    pub floor_price: Option<Amount>,
+    /// Sum of the prices of all completed sales of the collection in the currency.
+    pub total_volume: Amount,
+}
+
+/// The identifiers linking an NFT to its counterpart on an external chain.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct CrossChainIdentity {
+    /// The base64-encoded Linera token ID.
+    pub linera_token_id: String,
+    /// The NFT ID on the external chain.
+    pub external_id: u64,
+    pub chain_minter: String,
+    pub chain_owner: String,
+    pub token: String,
+}
+
+/// An NFT looked up by its external ID, together with its token ID in both encodings and its
+/// cross-chain identity.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct ExternalNftView {
+    pub nft: NftOutput,
+    /// The raw token ID.
+    pub token_id: TokenId,
+    /// The base64-encoded token ID, as accepted by queries and mutations.
+    pub token_id_base64: String,
+    pub identity: CrossChainIdentity,
+}
+
+/// The number of NFTs minted on this chain at a given time, sampled to compute mint rates.
+#[derive(Debug, Serialize, Deserialize, Clone, SimpleObject, PartialEq, Eq)]
+pub struct MintSample {
+    /// Number of NFTs ever minted on this chain.
+    pub count: u64,
+    /// Time at which the sample was taken.
+    pub at: Timestamp,
 }
 
 impl NftOutput {
@@ -147,8 +1073,15 @@ impl NftOutput {
             chain_minter: nft.chain_minter,
             chain_owner: nft.chain_owner,
             description: nft.description,
+            collection: nft.collection,
             blob_hash: nft.blob_hash,
             status: nft.status,
+            accepted_currencies: nft.accepted_currencies,
+            royalty_bps: nft.royalty_bps,
+            revealable: nft.revealable,
+            revealed: nft.revealed,
+            updated_at: nft.updated_at,
+            soulbound: nft.soulbound,
         }
     }
 
@@ -165,12 +1098,54 @@ impl NftOutput {
             chain_minter: nft.chain_minter,
             chain_owner: nft.chain_owner,
             description: nft.description,
+            collection: nft.collection,
             blob_hash: nft.blob_hash,
             status: nft.status,
+            accepted_currencies: nft.accepted_currencies,
+            royalty_bps: nft.royalty_bps,
+            revealable: nft.revealable,
+            revealed: nft.revealed,
+            updated_at: nft.updated_at,
+            soulbound: nft.soulbound,
+        }
+    }
+}
+
+/// How the total amount of a sale is divided.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SaleSplit {
+    /// The creator royalty, owed to the minter.
+    pub royalty: Amount,
+    /// The platform fee.
+    pub platform_fee: Amount,
+    /// What is left for the seller.
+    pub seller_proceeds: Amount,
+}
+
+impl SaleSplit {
+    /// Splits `total` into a royalty of `royalty_bps` and a platform fee of `platform_fee_bps`,
+    /// both rounded down, with the seller receiving the remainder including the rounding dust.
+    /// The fee is capped to what the royalty leaves, so the three parts always add up to
+    /// exactly `total`.
+    pub fn new(total: Amount, royalty_bps: u16, platform_fee_bps: u16) -> Self {
+        let royalty = basis_points_of(total, royalty_bps);
+        let after_royalty = total.saturating_sub(royalty);
+        let platform_fee = basis_points_of(total, platform_fee_bps).min(after_royalty);
+        SaleSplit {
+            royalty,
+            platform_fee,
+            seller_proceeds: after_royalty.saturating_sub(platform_fee),
         }
     }
 }
 
+/// Returns `bps` basis points of `total`, rounded down, without overflowing.
+pub fn basis_points_of(total: Amount, bps: u16) -> Amount {
+    let total = u128::from(total);
+    let bps = u128::from(bps);
+    Amount::from_attos(total / 10_000 * bps + total % 10_000 * bps / 10_000)
+}
+
 impl Display for TokenId {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.id)
@@ -178,6 +1153,64 @@ impl Display for TokenId {
 }
 
 impl Nft {
+    /// Returns the share of a sale of `total` owed to the creator as royalty.
+    pub fn royalty_on(&self, total: Amount) -> Amount {
+        basis_points_of(total, self.royalty_bps)
+    }
+
+    /// Returns whether a sale paid out in `currency` is acceptable for this NFT.
+    pub fn accepts_currency(&self, currency: &str) -> bool {
+        self.accepted_currencies
+            .as_ref()
+            .map_or(true, |currencies| currencies.contains(currency))
+    }
+
+    /// Checks that a payment of `amount` in `currency` covers the NFT's price. NFTs whose price
+    /// isn't a well-formed [`Amount`] only have their currency checked.
+    pub fn check_payment(&self, currency: &str, amount: &str) -> Result<(), NonFungibleTokenError> {
+        self.check_currency(currency)?;
+        let Some(price) = self.price_amount() else {
+            return Ok(());
+        };
+        let amount = amount
+            .parse::<Amount>()
+            .map_err(|_| NonFungibleTokenError::InvalidAmount(amount.to_owned()))?;
+        if amount < price {
+            return Err(NonFungibleTokenError::Underpaid { amount, price });
+        }
+        Ok(())
+    }
+
+    /// Checks that the NFT is priced in `currency`.
+    pub fn check_currency(&self, currency: &str) -> Result<(), NonFungibleTokenError> {
+        if currency != self.token {
+            return Err(NonFungibleTokenError::WrongCurrency {
+                expected: self.token.clone(),
+                currency: currency.to_owned(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Parses the listed `price` as an [`Amount`], if it is well-formed.
+    pub fn price_amount(&self) -> Option<Amount> {
+        self.price.parse().ok()
+    }
+
+    /// Checks that the NFT may change hands, i.e. isn't soulbound.
+    pub fn check_transferable(&self) -> Result<(), NonFungibleTokenError> {
+        if self.soulbound {
+            return Err(NonFungibleTokenError::Soulbound);
+        }
+        Ok(())
+    }
+
+    /// Derives the ID of a newly minted token by hashing its minting context and metadata.
+    ///
+    /// IDs are unique per application: `num_minted_nfts` differs for every mint on a chain, and
+    /// `minted_at`, the block timestamp of the mint, additionally separates NFTs with identical
+    /// fields minted in different blocks should the counter ever be reused. Mints in the same
+    /// block share a timestamp, so they remain distinguished by the counter alone.
     pub fn create_token_id(
         chain_id: &ChainId,
         application_id: &ApplicationId,
@@ -190,6 +1223,8 @@ impl Nft {
         id: u64,
         chain_minter: &String,
         chain_owner: &String,
+        minted_at: Timestamp,
+        soulbound: bool,
     ) -> Result<TokenId, bcs::Error> {
         use sha3::Digest as _;
 
@@ -206,9 +1241,317 @@ impl Nft {
         hasher.update(price.to_bcs_bytes()?);
         hasher.update(chain_owner.to_bcs_bytes()?);
         hasher.update(chain_minter.to_bcs_bytes()?);
+        hasher.update(minted_at.to_bcs_bytes()?);
+        hasher.update(soulbound.to_bcs_bytes()?);
 
         Ok(TokenId {
             id: hasher.finalize().to_vec(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::base::CryptoHash;
+
+    use super::*;
+
+    fn owner(seed: u64) -> AccountOwner {
+        AccountOwner::User(Owner(CryptoHash::from([seed; 4])))
+    }
+
+    fn nft(token: &str, price: &str) -> Nft {
+        Nft {
+            token_id: TokenId { id: vec![1] },
+            owner: owner(1),
+            name: "Test NFT".to_owned(),
+            minter: owner(1),
+            blob_hash: DataBlobHash(CryptoHash::from([1; 4])),
+            token: token.to_owned(),
+            price: price.to_owned(),
+            id: 1,
+            chain_minter: String::new(),
+            chain_owner: String::new(),
+            description: String::new(),
+            collection: String::new(),
+            status: NftStatus::OnSale,
+            accepted_currencies: None,
+            royalty_bps: 0,
+            revealable: false,
+            revealed: false,
+            updated_at: Timestamp::from(0),
+            soulbound: false,
+        }
+    }
+
+    fn fee_share(seed: u64, bps: u16) -> FeeShare {
+        FeeShare {
+            recipient: owner(seed),
+            bps,
+            address: format!("0x{seed:040x}"),
+        }
+    }
+
+    #[test]
+    fn sale_split_adds_up_to_total() {
+        for attos in [0, 1, 9_999, 10_001, 123_456_789, u128::MAX] {
+            let total = Amount::from_attos(attos);
+            for (royalty_bps, platform_fee_bps) in [(0, 0), (250, 100), (333, 777), (10_000, 0)] {
+                let split = SaleSplit::new(total, royalty_bps, platform_fee_bps);
+                let sum = u128::from(split.royalty)
+                    + u128::from(split.platform_fee)
+                    + u128::from(split.seller_proceeds);
+                assert_eq!(sum, attos);
+            }
+        }
+    }
+
+    #[test]
+    fn sale_split_rounds_down_in_favor_of_seller() {
+        let split = SaleSplit::new(Amount::from_attos(10_001), 250, 100);
+        assert_eq!(split.royalty, Amount::from_attos(250));
+        assert_eq!(split.platform_fee, Amount::from_attos(100));
+        assert_eq!(split.seller_proceeds, Amount::from_attos(9_651));
+    }
+
+    #[test]
+    fn sale_split_caps_fee_to_what_royalty_leaves() {
+        let total = Amount::from_tokens(1);
+        let split = SaleSplit::new(total, 9_500, 1_000);
+        assert_eq!(split.royalty, Amount::from_millis(950));
+        assert_eq!(split.platform_fee, Amount::from_millis(50));
+        assert_eq!(split.seller_proceeds, Amount::ZERO);
+    }
+
+    #[test]
+    fn basis_points_of_rounds_down_without_overflow() {
+        assert_eq!(basis_points_of(Amount::MAX, 10_000), Amount::MAX);
+        assert_eq!(basis_points_of(Amount::from_attos(9_999), 1), Amount::ZERO);
+        assert_eq!(
+            basis_points_of(Amount::from_tokens(2), 250),
+            Amount::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn platform_fee_shares_give_dust_to_last_recipient() {
+        let config = Config {
+            platform_fee_bps: 500,
+            fee_recipients: vec![fee_share(1, 7_000), fee_share(2, 3_000)],
+            ..Config::default()
+        };
+        let shares = config.platform_fee_shares(Amount::from_attos(11));
+        let amounts = shares.iter().map(|(_, amount)| *amount).collect::<Vec<_>>();
+        assert_eq!(amounts, [Amount::from_attos(7), Amount::from_attos(4)]);
+
+        let shares = config.platform_fee_shares(Amount::from_tokens(1));
+        let amounts = shares.iter().map(|(_, amount)| *amount).collect::<Vec<_>>();
+        assert_eq!(
+            amounts,
+            [Amount::from_millis(700), Amount::from_millis(300)]
+        );
+    }
+
+    #[test]
+    fn check_metadata_accepts_up_to_the_limits() {
+        let config = Config {
+            max_name_len: 4,
+            max_description_len: 8,
+            ..Config::default()
+        };
+        assert!(config.check_metadata("ñame", "12345678").is_ok());
+        assert!(matches!(
+            config.check_metadata("names", ""),
+            Err(NonFungibleTokenError::NameTooLong { len: 5, max: 4 })
+        ));
+        assert!(matches!(
+            config.check_metadata("", "123456789"),
+            Err(NonFungibleTokenError::DescriptionTooLong { len: 9, max: 8 })
+        ));
+    }
+
+    #[test]
+    fn check_mint_window_opens_early_for_allowlist() {
+        let config = Config {
+            mint_start_at: Some(Timestamp::from(2_000)),
+            allowlist_mint_start_at: Some(Timestamp::from(1_000)),
+            allowlist: BTreeSet::from([owner(1)]),
+            ..Config::default()
+        };
+        assert!(config
+            .check_mint_window(&owner(1), Timestamp::from(999))
+            .is_err());
+        assert!(config
+            .check_mint_window(&owner(1), Timestamp::from(1_000))
+            .is_ok());
+        assert!(matches!(
+            config.check_mint_window(&owner(2), Timestamp::from(1_999)),
+            Err(NonFungibleTokenError::MintNotStarted { .. })
+        ));
+        assert!(config
+            .check_mint_window(&owner(2), Timestamp::from(2_000))
+            .is_ok());
+        assert!(Config::default()
+            .check_mint_window(&owner(2), Timestamp::from(0))
+            .is_ok());
+    }
+
+    #[test]
+    fn address_formats() {
+        let evm = AddressFormat::Evm;
+        assert!(evm.is_valid("0x52908400098527886E0F7030069857D2E4169EE7"));
+        assert!(!evm.is_valid("52908400098527886E0F7030069857D2E4169EE7"));
+        assert!(!evm.is_valid("0x52908400098527886E0F7030069857D2E4169EE"));
+        assert!(!evm.is_valid("0x52908400098527886E0F7030069857D2E4169EEG"));
+
+        let base58 = AddressFormat::Base58;
+        assert!(base58.is_valid("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin"));
+        assert!(!base58.is_valid("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin1"));
+        assert!(!base58.is_valid("0xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin"));
+        assert!(!base58.is_valid("short"));
+    }
+
+    #[test]
+    fn check_payment() {
+        let nft = nft("ETH", "0.05");
+        assert!(nft.check_payment("ETH", "0.05").is_ok());
+        assert!(nft.check_payment("ETH", "1").is_ok());
+        assert!(matches!(
+            nft.check_payment("ETH", "0.049"),
+            Err(NonFungibleTokenError::Underpaid { .. })
+        ));
+        assert!(matches!(
+            nft.check_payment("ETH", "lots"),
+            Err(NonFungibleTokenError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            nft.check_payment("SOL", "0.05"),
+            Err(NonFungibleTokenError::WrongCurrency { .. })
+        ));
+
+        let unpriced = self::nft("ETH", "free");
+        assert!(unpriced.check_payment("ETH", "anything").is_ok());
+    }
+
+    #[test]
+    fn soulbound_nfts_are_not_transferable() {
+        let mut nft = nft("ETH", "0.05");
+        assert!(nft.check_transferable().is_ok());
+        nft.soulbound = true;
+        assert!(matches!(
+            nft.check_transferable(),
+            Err(NonFungibleTokenError::Soulbound)
+        ));
+    }
+
+    #[test]
+    fn check_batch_size_and_memo_accept_up_to_the_limits() {
+        let config = Config {
+            max_batch_size: 3,
+            max_memo_len: 5,
+            ..Config::default()
+        };
+        assert!(config.check_batch_size(3).is_ok());
+        assert!(matches!(
+            config.check_batch_size(4),
+            Err(NonFungibleTokenError::BatchTooLarge { len: 4, max: 3 })
+        ));
+        assert!(config.check_memo("héllo").is_ok());
+        assert!(matches!(
+            config.check_memo("hello!"),
+            Err(NonFungibleTokenError::MemoTooLong { len: 6, max: 5 })
+        ));
+    }
+
+    #[test]
+    fn check_flag_reason_accepts_up_to_the_limit() {
+        let config = Config {
+            max_flag_reason_len: 4,
+            ..Config::default()
+        };
+        assert!(config.check_flag_reason("spàm").is_ok());
+        assert!(matches!(
+            config.check_flag_reason("spams"),
+            Err(NonFungibleTokenError::FlagReasonTooLong { len: 5, max: 4 })
+        ));
+    }
+
+    #[test]
+    fn check_royalty_override() {
+        let config = Config {
+            max_royalty_bps: 1_000,
+            ..Config::default()
+        };
+        assert!(config.check_royalty_override(1_000).is_ok());
+        assert!(matches!(
+            config.check_royalty_override(1_001),
+            Err(NonFungibleTokenError::RoyaltyTooHigh {
+                bps: 1_001,
+                max: 1_000
+            })
+        ));
+
+        let enforced = Config {
+            royalty_mode: RoyaltyMode::Enforced,
+            ..config
+        };
+        assert!(matches!(
+            enforced.check_royalty_override(0),
+            Err(NonFungibleTokenError::RoyaltyOverrideNotAllowed)
+        ));
+    }
+
+    #[test]
+    fn check_limits_and_fee_recipients() {
+        let limits = ConfigLimits {
+            max_platform_fee_bps: 500,
+            max_royalty_bps: 1_000,
+        };
+        let config = Config {
+            max_royalty_bps: 1_000,
+            platform_fee_bps: 500,
+            fee_recipients: vec![fee_share(1, 7_000), fee_share(2, 3_000)],
+            ..Config::default()
+        };
+        assert!(config.check_limits(&limits).is_ok());
+
+        let too_expensive = Config {
+            platform_fee_bps: 501,
+            ..config.clone()
+        };
+        assert!(matches!(
+            too_expensive.check_limits(&limits),
+            Err(NonFungibleTokenError::PlatformFeeAboveLimit { bps: 501, max: 500 })
+        ));
+
+        let generous = Config {
+            max_royalty_bps: 1_001,
+            ..config.clone()
+        };
+        assert!(matches!(
+            generous.check_limits(&limits),
+            Err(NonFungibleTokenError::RoyaltyCapAboveLimit {
+                bps: 1_001,
+                max: 1_000
+            })
+        ));
+
+        let uneven = Config {
+            fee_recipients: vec![fee_share(1, 7_000), fee_share(2, 2_999)],
+            ..config
+        };
+        assert!(matches!(
+            uneven.check_fee_recipients(),
+            Err(NonFungibleTokenError::InvalidFeeSplit { total: 9_999 })
+        ));
+        assert!(matches!(
+            Config {
+                platform_fee_bps: 10_001,
+                ..Config::default()
+            }
+            .check_fee_recipients(),
+            Err(NonFungibleTokenError::PlatformFeeTooHigh(10_001))
+        ));
+        assert!(Config::default().check_fee_recipients().is_ok());
+    }
+}
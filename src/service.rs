@@ -10,15 +10,20 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
+use async_graphql::{EmptySubscription, Json, Object, Request, Response, Result, Schema};
 use base64::engine::{general_purpose::STANDARD_NO_PAD, Engine as _};
 use fungible::Account;
 use linera_sdk::{
-    base::{AccountOwner, WithServiceAbi},
+    base::{AccountOwner, Amount, TimeDelta, Timestamp, WithServiceAbi},
     views::View,
     DataBlobHash, Service, ServiceRuntime,
 };
-use non_fungible::{NftOutput, Operation, TokenId};
+use non_fungible::{
+    AccountVolume, CollectionCurrency, CollectionMeta, CollectionStats, Config, CrossChainIdentity,
+    ExportPage, ExternalNftView, Flag, Listing, MarketEvent, MintSample, MintVoucher,
+    ModerationAction, Nft, NftOutput, NftStatus, Operation, OutboundTransfer, PriceStats,
+    SupplyStats, TokenId, TransferStatus, API_VERSION, SUPPORTED_OPERATIONS,
+};
 
 use self::state::NonFungibleTokenState;
 
@@ -67,12 +72,29 @@ struct QueryRoot {
 
 #[Object]
 impl QueryRoot {
-    async fn nft(&self, token_id: String) -> Option<NftOutput> {
-        let token_id_vec = STANDARD_NO_PAD.decode(&token_id).unwrap();
+    /// Returns the version of the API, to let clients detect capabilities.
+    async fn api_version(&self) -> String {
+        API_VERSION.to_owned()
+    }
+
+    /// Returns the names of the operations supported by this version.
+    async fn supported_operations(&self) -> Vec<String> {
+        SUPPORTED_OPERATIONS
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Returns the application configuration, including its limits and royalty mode.
+    async fn config(&self) -> Config {
+        self.non_fungible_token.config.get().clone()
+    }
+
+    async fn nft(&self, token_id: String) -> Result<Option<NftOutput>> {
         let nft = self
             .non_fungible_token
             .nfts
-            .get(&TokenId { id: token_id_vec })
+            .get(&decode_token_id(&token_id)?)
             .await
             .unwrap();
 
@@ -85,14 +107,145 @@ impl QueryRoot {
                 runtime.read_data_blob(nft.blob_hash)
             };
             let nft_output = NftOutput::new_with_token_id(token_id, nft, payload);
-            Some(nft_output)
+            Ok(Some(nft_output))
         } else {
-            None
+            Ok(None)
         }
     }
 
+    /// Returns the NFT with the given hex-encoded token ID, as produced by some tools, as an
+    /// alternative to the base64 token IDs taken by `nft`.
+    async fn nft_by_hex_id(&self, hex: String) -> Result<Option<NftOutput>> {
+        let token_id = decode_hex_token_id(&hex)?;
+        let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+            return Ok(None);
+        };
+        let payload = {
+            let mut runtime = self
+                .runtime
+                .try_lock()
+                .expect("Services only run in a single thread");
+            runtime.read_data_blob(nft.blob_hash)
+        };
+        Ok(Some(NftOutput::new(nft, payload)))
+    }
+
+    /// Returns the NFT exactly as stored, including its raw token ID bytes, without reading
+    /// its blob.
+    async fn nft_raw(&self, token_id: String) -> Result<Option<Nft>> {
+        Ok(self
+            .non_fungible_token
+            .nfts
+            .get(&decode_token_id(&token_id)?)
+            .await
+            .unwrap())
+    }
+
+    /// Returns the hex-encoded hash of the blob backing an NFT, without reading the blob.
+    async fn blob_hash_of(&self, token_id: String) -> Result<Option<String>> {
+        Ok(self
+            .non_fungible_token
+            .nfts
+            .get(&decode_token_id(&token_id)?)
+            .await
+            .unwrap()
+            .map(|nft| nft.blob_hash.0.to_string()))
+    }
+
+    /// Returns the identifiers linking an NFT to the external chain, without reading its blob.
+    async fn cross_chain_identity(&self, token_id: String) -> Result<Option<CrossChainIdentity>> {
+        Ok(self
+            .non_fungible_token
+            .nfts
+            .get(&decode_token_id(&token_id)?)
+            .await
+            .unwrap()
+            .map(|nft| CrossChainIdentity {
+                linera_token_id: token_id,
+                external_id: nft.id,
+                chain_minter: nft.chain_minter,
+                chain_owner: nft.chain_owner,
+                token: nft.token,
+            }))
+    }
+
+    /// Returns the number of bytes an NFT takes in storage: its BCS-serialized record plus its
+    /// blob.
+    async fn nft_storage_size(&self, token_id: String) -> Result<Option<u64>> {
+        let Some(nft) = self
+            .non_fungible_token
+            .nfts
+            .get(&decode_token_id(&token_id)?)
+            .await
+            .unwrap()
+        else {
+            return Ok(None);
+        };
+        let blob_size = {
+            let mut runtime = self
+                .runtime
+                .try_lock()
+                .expect("Services only run in a single thread");
+            runtime.read_data_blob(nft.blob_hash).len()
+        };
+        let record_size = bcs::to_bytes(&nft)?.len();
+        Ok(Some((record_size + blob_size) as u64))
+    }
+
+    /// Returns the total size in bytes of the blobs referenced by NFTs on this chain, counting
+    /// blobs shared by several NFTs once. The runtime can't report a blob's size without
+    /// reading it, so this reads every referenced blob in full and is expensive on large
+    /// collections.
+    async fn total_blob_bytes(&self) -> u64 {
+        let mut blob_hashes = BTreeSet::new();
+        self.non_fungible_token
+            .nfts
+            .for_each_index_value(|_token_id, nft| {
+                blob_hashes.insert(nft.blob_hash);
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let mut runtime = self
+            .runtime
+            .try_lock()
+            .expect("Services only run in a single thread");
+        blob_hashes
+            .into_iter()
+            .map(|blob_hash| runtime.read_data_blob(blob_hash).len() as u64)
+            .sum()
+    }
+
+    /// Returns the alternative prices of a listed NFT, if it was listed with several.
+    async fn listings(&self, token_id: String) -> Result<Vec<Listing>> {
+        Ok(self
+            .non_fungible_token
+            .listings
+            .get(&decode_token_id(&token_id)?)
+            .await
+            .unwrap()
+            .unwrap_or_default())
+    }
+
+    /// Returns the moderation reports filed against an NFT, oldest first.
+    async fn flags(&self, token_id: String) -> Result<Vec<Flag>> {
+        Ok(self
+            .non_fungible_token
+            .flags
+            .get(&decode_token_id(&token_id)?)
+            .await
+            .unwrap()
+            .unwrap_or_default())
+    }
+
     async fn nftUsingBlobHash(&self, id: u64) -> Option<NftOutput> {
-        let token_id = self.non_fungible_token.blob_token_ids.get(&id).await.unwrap();
+        let token_id = self
+            .non_fungible_token
+            .blob_token_ids
+            .get(&id)
+            .await
+            .unwrap();
 
         let nft = self
             .non_fungible_token
@@ -109,18 +262,193 @@ impl QueryRoot {
                     .expect("Services only run in a single thread");
                 runtime.read_data_blob(nft.blob_hash)
             };
-            let nft_output = NftOutput::new_with_token_id(token_id.unwrap().to_string(), nft, payload);
+            let nft_output =
+                NftOutput::new_with_token_id(token_id.unwrap().to_string(), nft, payload);
             Some(nft_output)
         } else {
             None
         }
     }
 
+    /// Returns the NFT mapped to an external ID, with its token ID in raw and base64 form and
+    /// its cross-chain identity.
+    async fn nft_by_external_id_full(&self, id: u64) -> Option<ExternalNftView> {
+        let token_id = self
+            .non_fungible_token
+            .blob_token_ids
+            .get(&id)
+            .await
+            .unwrap()?;
+        let nft = self.non_fungible_token.nfts.get(&token_id).await.unwrap()?;
+
+        let token_id_base64 = STANDARD_NO_PAD.encode(&token_id.id);
+        let identity = CrossChainIdentity {
+            linera_token_id: token_id_base64.clone(),
+            external_id: nft.id,
+            chain_minter: nft.chain_minter.clone(),
+            chain_owner: nft.chain_owner.clone(),
+            token: nft.token.clone(),
+        };
+        let payload = {
+            let mut runtime = self
+                .runtime
+                .try_lock()
+                .expect("Services only run in a single thread");
+            runtime.read_data_blob(nft.blob_hash)
+        };
+        Some(ExternalNftView {
+            nft: NftOutput::new_with_token_id(token_id_base64.clone(), nft, payload),
+            token_id,
+            token_id_base64,
+            identity,
+        })
+    }
+
+    /// Returns the NFTs mapped to the given external IDs, keyed by external ID. Unknown IDs are
+    /// omitted, and each blob is read only once even if several NFTs share it.
+    async fn nfts_by_external_ids(&self, ids: Vec<u64>) -> BTreeMap<u64, NftOutput> {
+        let mut payloads = BTreeMap::<DataBlobHash, Vec<u8>>::new();
+        let mut nfts = BTreeMap::new();
+        for id in ids {
+            if nfts.contains_key(&id) {
+                continue;
+            }
+            let Some(token_id) = self
+                .non_fungible_token
+                .blob_token_ids
+                .get(&id)
+                .await
+                .unwrap()
+            else {
+                continue;
+            };
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            let payload = payloads
+                .entry(nft.blob_hash)
+                .or_insert_with(|| {
+                    let mut runtime = self
+                        .runtime
+                        .try_lock()
+                        .expect("Services only run in a single thread");
+                    runtime.read_data_blob(nft.blob_hash)
+                })
+                .clone();
+            nfts.insert(id, NftOutput::new(nft, payload));
+        }
+
+        nfts
+    }
+
     async fn nfts(&self) -> BTreeMap<String, NftOutput> {
         let mut nfts = BTreeMap::new();
         self.non_fungible_token
             .nfts
             .for_each_index_value(|_token_id, nft| {
+                if !is_publicly_visible(&nft) {
+                    return Ok(());
+                }
+                let nft = nft.into_owned();
+                let payload = {
+                    let mut runtime = self
+                        .runtime
+                        .try_lock()
+                        .expect("Services only run in a single thread");
+                    runtime.read_data_blob(nft.blob_hash)
+                };
+                let nft_output = NftOutput::new(nft, payload);
+                nfts.insert(nft_output.token_id.clone(), nft_output);
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        nfts
+    }
+
+    /// Returns up to `limit` NFTs, most recently minted, transferred, listed or updated first.
+    /// Blobs are only read for the returned NFTs.
+    async fn recently_active(&self, limit: u32) -> Vec<NftOutput> {
+        let mut nfts = Vec::new();
+        self.non_fungible_token
+            .nfts
+            .for_each_index_value(|_token_id, nft| {
+                if is_publicly_visible(&nft) {
+                    nfts.push(nft.into_owned());
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        nfts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+        nfts.into_iter()
+            .take(limit as usize)
+            .map(|nft| {
+                let payload = {
+                    let mut runtime = self
+                        .runtime
+                        .try_lock()
+                        .expect("Services only run in a single thread");
+                    runtime.read_data_blob(nft.blob_hash)
+                };
+                NftOutput::new(nft, payload)
+            })
+            .collect()
+    }
+
+    /// Returns `count` NFTs sampled from the whole collection. The sample only depends on the
+    /// `seed` and the set of NFTs, so the same seed returns the same NFTs until the collection
+    /// changes. Blobs are only read for the sampled NFTs.
+    async fn random_nfts(&self, count: u32, seed: u64) -> Vec<NftOutput> {
+        let hidden = self
+            .non_fungible_token
+            .status_token_ids
+            .get(&NftStatus::Hidden)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        let mut token_ids = self.non_fungible_token.nfts.indices().await.unwrap();
+        token_ids.retain(|token_id| !hidden.contains(token_id));
+        let count = (count as usize).min(token_ids.len());
+        let mut state = seed;
+        for index in 0..count {
+            let offset = (splitmix64(&mut state) % (token_ids.len() - index) as u64) as usize;
+            token_ids.swap(index, index + offset);
+        }
+
+        let mut nfts = Vec::with_capacity(count);
+        for token_id in token_ids.into_iter().take(count) {
+            let nft = self
+                .non_fungible_token
+                .nfts
+                .get(&token_id)
+                .await
+                .unwrap()
+                .expect("Indexed NFT must exist");
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            nfts.push(NftOutput::new(nft, payload));
+        }
+
+        nfts
+    }
+
+    /// Returns the revealable NFTs that still point at their placeholder blob.
+    async fn unrevealed(&self) -> BTreeMap<String, NftOutput> {
+        let mut nfts = BTreeMap::new();
+        self.non_fungible_token
+            .nfts
+            .for_each_index_value(|_token_id, nft| {
+                if !nft.revealable || nft.revealed || !is_publicly_visible(&nft) {
+                    return Ok(());
+                }
                 let nft = nft.into_owned();
                 let payload = {
                     let mut runtime = self
@@ -139,6 +467,41 @@ impl QueryRoot {
         nfts
     }
 
+    /// Returns the NFTs with the given token IDs, keyed by token ID. Unknown IDs are omitted,
+    /// and each blob is read only once even if several NFTs share it.
+    async fn nfts_by_ids(&self, token_ids: Vec<String>) -> Result<BTreeMap<String, NftOutput>> {
+        let mut payloads = BTreeMap::<DataBlobHash, Vec<u8>>::new();
+        let mut nfts = BTreeMap::new();
+        for token_id in token_ids {
+            if nfts.contains_key(&token_id) {
+                continue;
+            }
+            let Some(nft) = self
+                .non_fungible_token
+                .nfts
+                .get(&decode_token_id(&token_id)?)
+                .await
+                .unwrap()
+            else {
+                continue;
+            };
+            let payload = payloads
+                .entry(nft.blob_hash)
+                .or_insert_with(|| {
+                    let mut runtime = self
+                        .runtime
+                        .try_lock()
+                        .expect("Services only run in a single thread");
+                    runtime.read_data_blob(nft.blob_hash)
+                })
+                .clone();
+            let nft_output = NftOutput::new_with_token_id(token_id.clone(), nft, payload);
+            nfts.insert(token_id, nft_output);
+        }
+
+        Ok(nfts)
+    }
+
     async fn owned_token_ids_by_owner(&self, owner: AccountOwner) -> BTreeSet<String> {
         self.non_fungible_token
             .owned_token_ids
@@ -171,114 +534,1091 @@ impl QueryRoot {
         owners
     }
 
-    async fn owned_nfts(&self, owner: AccountOwner) -> BTreeMap<String, NftOutput> {
-        let mut result = BTreeMap::new();
-        let owned_token_ids = self
+    /// Returns up to `limit` of an owner's token IDs (50 by default), in token ID order,
+    /// starting after the `after` token ID. Pass the last returned ID as `after` to fetch the
+    /// next page.
+    async fn owned_token_ids_list(
+        &self,
+        owner: AccountOwner,
+        after: Option<String>,
+        #[graphql(default = 50)] limit: u32,
+    ) -> Result<Vec<String>> {
+        let after = after.as_deref().map(decode_token_id).transpose()?;
+        let token_ids = self
             .non_fungible_token
             .owned_token_ids
             .get(&owner)
             .await
-            .unwrap();
+            .unwrap()
+            .unwrap_or_default();
 
-        for token_id in owned_token_ids.into_iter().flatten() {
+        Ok(token_ids
+            .into_iter()
+            .filter(|token_id| after.as_ref().map_or(true, |after| token_id > after))
+            .take(limit as usize)
+            .map(|token_id| STANDARD_NO_PAD.encode(token_id.id))
+            .collect())
+    }
+
+    /// Returns the token IDs of up to `limit` owners (50 by default), in owner order, starting
+    /// after the `after` owner. Pass the last returned owner as `after` to fetch the next page.
+    async fn owned_token_ids_paged(
+        &self,
+        after: Option<AccountOwner>,
+        #[graphql(default = 50)] limit: u32,
+    ) -> BTreeMap<AccountOwner, BTreeSet<String>> {
+        let owners = self
+            .non_fungible_token
+            .owned_token_ids
+            .indices()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|owner| after.map_or(true, |after| *owner > after))
+            .collect::<BTreeSet<_>>();
+
+        let mut page = BTreeMap::new();
+        for owner in owners.into_iter().take(limit as usize) {
+            let token_ids = self
+                .non_fungible_token
+                .owned_token_ids
+                .get(&owner)
+                .await
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(|token_id| STANDARD_NO_PAD.encode(token_id.id))
+                .collect();
+            page.insert(owner, token_ids);
+        }
+
+        page
+    }
+
+    /// Returns up to `limit` raw NFT records (100 by default), in token ID order, starting after
+    /// the `after` cursor. Pass the returned `next` cursor as `after` to fetch the following
+    /// page, until it is `None`. No blobs are read, so whole collections can be exported cheaply.
+    async fn export_nfts(
+        &self,
+        after: Option<String>,
+        #[graphql(default = 100)] limit: u32,
+    ) -> Result<ExportPage> {
+        let after = after.as_deref().map(decode_token_id).transpose()?;
+        let token_ids = self
+            .non_fungible_token
+            .nfts
+            .indices()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|token_id| after.as_ref().map_or(true, |after| token_id > after))
+            .collect::<BTreeSet<_>>();
+
+        let mut items = Vec::new();
+        for token_id in token_ids.iter().take(limit as usize) {
             let nft = self
                 .non_fungible_token
                 .nfts
-                .get(&token_id)
+                .get(token_id)
                 .await
                 .unwrap()
-                .unwrap();
-            let payload = {
-                let mut runtime = self
-                    .runtime
-                    .try_lock()
-                    .expect("Services only run in a single thread");
-                runtime.read_data_blob(nft.blob_hash)
-            };
-            let nft_output = NftOutput::new(nft, payload);
-            result.insert(nft_output.token_id.clone(), nft_output);
+                .expect("Indexed NFT must exist");
+            items.push(nft);
         }
+        let next = match items.last() {
+            Some(last) if token_ids.len() > items.len() => {
+                Some(STANDARD_NO_PAD.encode(&last.token_id.id))
+            }
+            _ => None,
+        };
 
-        result
+        Ok(ExportPage { items, next })
     }
-}
 
-struct MutationRoot;
+    /// Returns up to `limit` sellers with the highest total volume of completed sales, highest
+    /// first. Volumes add up sale prices across currencies, and sales whose transfer bounced
+    /// are not counted.
+    async fn top_sellers(&self, limit: u32) -> Vec<AccountVolume> {
+        let mut leaderboard = Vec::new();
+        self.non_fungible_token
+            .seller_volume
+            .for_each_index_value(|account, volume| {
+                leaderboard.push(AccountVolume {
+                    account,
+                    volume: *volume,
+                });
+                Ok(())
+            })
+            .await
+            .unwrap();
+        leaderboard.sort_by(|a, b| b.volume.cmp(&a.volume));
+        leaderboard.truncate(limit as usize);
 
-#[Object]
-impl MutationRoot {
-    async fn mint(&self, minter: AccountOwner, name: String, blob_hash: DataBlobHash,
-                  token: String, // ETH, SOL
-                  price: String, // 0.05 [token]
-                  id: u64, // specific chain nft id
-                  chain_minter: String, // chain nft minter
-                  chain_owner: String, // chain nft owner
-                  description: String,
-                  ) -> Vec<u8> {
-        bcs::to_bytes(&Operation::Mint {
-            minter,
-            name,
-            blob_hash,
-            token,
-            price,
-            id,
-            chain_owner,
-            chain_minter,
-            description,
-        })
-        .unwrap()
+        leaderboard
     }
 
-    async fn transfer(
-        &self,
-        source_owner: AccountOwner,
-        token_id: String,
-        target_account: Account,
-        chain_owner: String,
-        buy_from_token: String,
-        to_token: String,
-        amount: String,
-    ) -> Vec<u8> {
-        bcs::to_bytes(&Operation::Transfer {
-            source_owner,
-            token_id: TokenId {
-                id: STANDARD_NO_PAD.decode(token_id).unwrap(),
-            },
-            target_account,
-            chain_owner,
-            buy_from_token,
-            to_token,
-            amount,
-        })
-        .unwrap()
-    }
+    /// Returns up to `limit` buyers with the highest total volume of completed purchases,
+    /// highest first. Volumes add up sale prices across currencies, and purchases whose
+    /// transfer bounced are not counted.
+    async fn top_buyers(&self, limit: u32) -> Vec<AccountVolume> {
+        let mut leaderboard = Vec::new();
+        self.non_fungible_token
+            .buyer_volume
+            .for_each_index_value(|account, volume| {
+                leaderboard.push(AccountVolume {
+                    account,
+                    volume: *volume,
+                });
+                Ok(())
+            })
+            .await
+            .unwrap();
+        leaderboard.sort_by(|a, b| b.volume.cmp(&a.volume));
+        leaderboard.truncate(limit as usize);
 
-    async fn claim(
-        &self,
-        source_account: Account,
-        token_id: String,
-        target_account: Account,
-    ) -> Vec<u8> {
-        bcs::to_bytes(&Operation::Claim {
-            source_account,
-            token_id: TokenId {
-                id: STANDARD_NO_PAD.decode(token_id).unwrap(),
-            },
-            target_account,
-        })
-        .unwrap()
+        leaderboard
     }
 
-    async fn listNftForSale(
-        &self,
+    /// Returns the number of owners currently holding at least one NFT.
+    async fn holder_count(&self) -> u64 {
+        let mut count = 0;
+        self.non_fungible_token
+            .owned_token_ids
+            .for_each_index_value(|_owner, token_ids| {
+                if !token_ids.is_empty() {
+                    count += 1;
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        count
+    }
+
+    /// Returns a histogram of holdings, mapping each number of NFTs held to the number of
+    /// owners holding exactly that many. Owners without NFTs are not counted.
+    async fn ownership_distribution(&self) -> BTreeMap<u32, u32> {
+        let mut distribution = BTreeMap::new();
+        self.non_fungible_token
+            .owned_token_ids
+            .for_each_index_value(|_owner, token_ids| {
+                if !token_ids.is_empty() {
+                    *distribution.entry(token_ids.len() as u32).or_insert(0) += 1;
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        distribution
+    }
+
+    /// Returns the lowest price among the NFTs of a collection on sale in the given currency.
+    async fn floor_price(&self, collection: String, currency: String) -> Option<String> {
+        let token_ids = self
+            .non_fungible_token
+            .collection_token_ids
+            .get(&collection)
+            .await
+            .unwrap()?;
+
+        let mut floor_price: Option<Amount> = None;
+        for token_id in token_ids {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if nft.status != NftStatus::OnSale || nft.token != currency {
+                continue;
+            }
+            if let Some(price) = nft.price_amount() {
+                floor_price = Some(floor_price.map_or(price, |floor_price| floor_price.min(price)));
+            }
+        }
+
+        floor_price.map(|price| price.to_string())
+    }
+
+    /// Returns the token IDs of the NFTs owned by the given address on the external chain.
+    async fn token_ids_by_chain_owner(&self, chain_owner: String) -> BTreeSet<String> {
+        self.non_fungible_token
+            .chain_owner_token_ids
+            .get(&chain_owner)
+            .await
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .map(|token_id| STANDARD_NO_PAD.encode(token_id.id))
+            .collect()
+    }
+
+    /// Returns the NFTs of `owner` that belong to `collection`, keyed by token ID.
+    async fn owned_in_collection(
+        &self,
+        owner: AccountOwner,
+        collection: String,
+    ) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let owned = self
+            .non_fungible_token
+            .owned_token_ids
+            .get(&owner)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        let in_collection = self
+            .non_fungible_token
+            .collection_token_ids
+            .get(&collection)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+
+        for token_id in owned.intersection(&in_collection) {
+            let Some(nft) = self.non_fungible_token.nfts.get(token_id).await.unwrap() else {
+                continue;
+            };
+            if !is_publicly_visible(&nft) {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+
+    /// Returns the NFTs credited to the given minter.
+    async fn nfts_by_minter(&self, minter: AccountOwner) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let token_ids = self
+            .non_fungible_token
+            .minter_token_ids
+            .get(&minter)
+            .await
+            .unwrap();
+
+        for token_id in token_ids.into_iter().flatten() {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if !is_publicly_visible(&nft) {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+
+    /// Returns the display metadata of a collection, if it has been set.
+    async fn collection_meta(&self, collection: String) -> Option<CollectionMeta> {
+        self.non_fungible_token
+            .collection_metadata
+            .get(&collection)
+            .await
+            .unwrap()
+    }
+
+    /// Returns the number of NFTs in each collection, without reading any NFT or blob.
+    async fn collection_counts(&self) -> BTreeMap<String, u64> {
+        let mut counts = BTreeMap::new();
+        self.non_fungible_token
+            .collection_token_ids
+            .for_each_index_value(|collection, token_ids| {
+                counts.insert(collection, token_ids.len() as u64);
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        counts
+    }
+
+    /// Returns the item and owner counts of a collection, and its floor price and total sales
+    /// volume in `currency`.
+    async fn collection_stats(&self, collection: String, currency: String) -> CollectionStats {
+        let token_ids = self
+            .non_fungible_token
+            .collection_token_ids
+            .get(&collection)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+
+        let mut item_count = 0;
+        let mut owners = BTreeSet::new();
+        let mut floor_price: Option<Amount> = None;
+        for token_id in token_ids {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            item_count += 1;
+            owners.insert(nft.owner);
+            if nft.status != NftStatus::OnSale || nft.token != currency {
+                continue;
+            }
+            if let Some(price) = nft.price_amount() {
+                floor_price = Some(floor_price.map_or(price, |floor_price| floor_price.min(price)));
+            }
+        }
+        let total_volume = self
+            .non_fungible_token
+            .collection_volumes
+            .get(&CollectionCurrency {
+                collection,
+                currency,
+            })
+            .await
+            .unwrap()
+            .unwrap_or_default();
+
+        CollectionStats {
+            item_count,
+            owner_count: owners.len() as u64,
+            floor_price,
+            total_volume,
+        }
+    }
+
+    /// Returns the NFTs on sale whose listing expires within `within_secs` from now, soonest
+    /// first. Blobs are only read for matching NFTs.
+    async fn listings_ending_soon(&self, within_secs: u64) -> Vec<NftOutput> {
+        let now = self
+            .runtime
+            .try_lock()
+            .expect("Services only run in a single thread")
+            .system_time();
+        let deadline = now.saturating_add(TimeDelta::from_secs(within_secs));
+        let mut ending = Vec::new();
+        self.non_fungible_token
+            .listing_expiries
+            .for_each_index_value(|token_id, expires_at| {
+                let expires_at = *expires_at;
+                if now <= expires_at && expires_at <= deadline {
+                    ending.push((expires_at, token_id));
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        ending.sort();
+
+        let mut result = Vec::new();
+        for (_expires_at, token_id) in ending {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if nft.status != NftStatus::OnSale {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            result.push(NftOutput::new(nft, payload));
+        }
+
+        result
+    }
+
+    /// Returns the NFTs credited to the given minter that are now owned by someone else.
+    async fn minted_not_owned(&self, minter: AccountOwner) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let token_ids = self
+            .non_fungible_token
+            .minter_token_ids
+            .get(&minter)
+            .await
+            .unwrap();
+
+        for token_id in token_ids.into_iter().flatten() {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if nft.owner == minter || !is_publicly_visible(&nft) {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+
+    /// Returns the NFTs that aren't listed for sale, either since their mint or since their
+    /// listing was cancelled, using the status index.
+    async fn nfts_not_listed(&self) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let token_ids = self
+            .non_fungible_token
+            .status_token_ids
+            .get(&NftStatus::NotListed)
+            .await
+            .unwrap();
+
+        for token_id in token_ids.into_iter().flatten() {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+
+    /// Returns all publicly visible NFTs partitioned by status. Payloads are left empty to avoid
+    /// reading blobs.
+    async fn nfts_grouped_by_status(&self) -> BTreeMap<NftStatus, Vec<NftOutput>> {
+        let mut status_token_ids = Vec::new();
+        self.non_fungible_token
+            .status_token_ids
+            .for_each_index_value(|status, token_ids| {
+                status_token_ids.push((status, token_ids.into_owned()));
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let mut groups = BTreeMap::new();
+        for (status, token_ids) in status_token_ids {
+            if status == NftStatus::Hidden {
+                continue;
+            }
+            let mut nfts = Vec::new();
+            for token_id in token_ids {
+                if let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() {
+                    nfts.push(NftOutput::new(nft, Vec::new()));
+                }
+            }
+            groups.insert(status, nfts);
+        }
+
+        groups
+    }
+
+    /// Returns the NFTs with the given status that are priced in `currency`. Blobs are only
+    /// read for matching NFTs.
+    async fn nfts_by_currency_and_status(
+        &self,
+        currency: String,
+        status: NftStatus,
+    ) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let token_ids = self
+            .non_fungible_token
+            .status_token_ids
+            .get(&status)
+            .await
+            .unwrap();
+
+        for token_id in token_ids.into_iter().flatten() {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if nft.token != currency || !is_publicly_visible(&nft) {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+
+    /// Returns the total value of the NFTs held by `owner`, at their prices in `currency`. NFTs
+    /// priced in another currency count at their alternative listing in `currency` if they
+    /// have one, and are left out otherwise: the universal solver exposes no quotes to convert
+    /// prices with, so no cross-currency conversion is attempted.
+    async fn portfolio_value(&self, owner: AccountOwner, currency: String) -> Amount {
+        let token_ids = self
+            .non_fungible_token
+            .owned_token_ids
+            .get(&owner)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+
+        let mut total = Amount::ZERO;
+        for token_id in token_ids {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            let price = if nft.token == currency {
+                nft.price_amount()
+            } else {
+                self.non_fungible_token
+                    .listings
+                    .get(&token_id)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .flatten()
+                    .find(|listing| listing.currency == currency)
+                    .and_then(|listing| listing.price.parse::<Amount>().ok())
+            };
+            total = total.saturating_add(price.unwrap_or_default());
+        }
+
+        total
+    }
+
+    /// Returns the minimum, maximum and last sale prices and the sale count in `currency`.
+    async fn price_stats(&self, currency: String) -> Option<PriceStats> {
+        self.non_fungible_token
+            .price_stats
+            .get(&currency)
+            .await
+            .unwrap()
+    }
+
+    /// Returns up to `limit` marketplace events with a sequence number greater than `after`,
+    /// in order. Pass the last returned sequence number as `after` to fetch the next page.
+    async fn events(&self, after: u64, limit: u32) -> Vec<MarketEvent> {
+        let count = self.non_fungible_token.events.count();
+        let start = usize::try_from(after).unwrap_or(usize::MAX).min(count);
+        let end = start.saturating_add(limit as usize).min(count);
+        self.non_fungible_token
+            .events
+            .read(start..end)
+            .await
+            .unwrap()
+    }
+
+    /// Returns the latest `limit` marketplace events in which `owner` took part as minter,
+    /// seller or buyer, in order.
+    async fn events_for_owner(&self, owner: AccountOwner, limit: u32) -> Vec<MarketEvent> {
+        let sequences = self
+            .non_fungible_token
+            .owner_events
+            .get(&owner)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+
+        let skip = sequences.len().saturating_sub(limit as usize);
+        let mut events = Vec::new();
+        for sequence in sequences.into_iter().skip(skip) {
+            let index = usize::try_from(sequence - 1).unwrap();
+            if let Some(event) = self.non_fungible_token.events.get(index).await.unwrap() {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Returns the minted, active and burned NFT counters, to help verify supply invariants.
+    async fn supply_stats(&self) -> SupplyStats {
+        let currently_active = self.non_fungible_token.nfts.count().await.unwrap();
+        SupplyStats {
+            ever_minted: *self.non_fungible_token.num_minted_nfts.get(),
+            currently_active: currently_active as u64,
+            burned: *self.non_fungible_token.num_burned.get(),
+        }
+    }
+
+    /// Returns the BCS bytes of any operation, given in its JSON form, e.g.
+    /// `{"Burn": {"token_id": {"id": [...]}}}`. This lets tooling build operations that have
+    /// no dedicated mutation.
+    async fn encode_operation(&self, operation: Json<Operation>) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&operation.0)?)
+    }
+
+    /// Returns the number of NFTs minted so far together with the current time. Clients can
+    /// sample this periodically to compute mint rates.
+    async fn mint_rate_sample(&self) -> MintSample {
+        let at = self
+            .runtime
+            .try_lock()
+            .expect("Services only run in a single thread")
+            .system_time();
+        MintSample {
+            count: *self.non_fungible_token.num_minted_nfts.get(),
+            at,
+        }
+    }
+
+    /// Returns the NFTs of the given owner. Token IDs in the owner index that no longer
+    /// resolve to an NFT are skipped.
+    async fn owned_nfts(&self, owner: AccountOwner) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let owned_token_ids = self
+            .non_fungible_token
+            .owned_token_ids
+            .get(&owner)
+            .await
+            .unwrap();
+
+        for token_id in owned_token_ids.into_iter().flatten() {
+            // Skip stale index entries instead of failing the whole query.
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if !is_publicly_visible(&nft) {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+
+    /// Returns whether an NFT is on its way to or from this chain.
+    async fn transfer_status(&self, token_id: String) -> Result<TransferStatus> {
+        let token_id = decode_token_id(&token_id)?;
+        let is_outbound = self
+            .non_fungible_token
+            .outbound_transfers
+            .contains_key(&token_id)
+            .await
+            .unwrap();
+        if is_outbound {
+            return Ok(TransferStatus::Outbound);
+        }
+        let is_inbound = self
+            .non_fungible_token
+            .pending_in
+            .contains_key(&token_id)
+            .await
+            .unwrap();
+        if is_inbound {
+            return Ok(TransferStatus::Inbound);
+        }
+        Ok(TransferStatus::Idle)
+    }
+
+    /// Returns the NFTs the given owner sent to another chain that haven't been acknowledged or
+    /// bounced yet. They no longer appear in `ownedNfts` while in transit.
+    async fn in_transit_nfts(&self, owner: AccountOwner) -> BTreeMap<String, OutboundTransfer> {
+        let mut result = BTreeMap::new();
+        self.non_fungible_token
+            .outbound_transfers
+            .for_each_index_value(|token_id, outbound_transfer| {
+                if outbound_transfer.nft.owner == owner {
+                    let token_id = STANDARD_NO_PAD.encode(token_id.id);
+                    result.insert(token_id, outbound_transfer.into_owned());
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        result
+    }
+
+    /// Returns the NFTs of the given owner that are currently on sale.
+    async fn owned_on_sale(&self, owner: AccountOwner) -> BTreeMap<String, NftOutput> {
+        let mut result = BTreeMap::new();
+        let owned_token_ids = self
+            .non_fungible_token
+            .owned_token_ids
+            .get(&owner)
+            .await
+            .unwrap();
+
+        for token_id in owned_token_ids.into_iter().flatten() {
+            let Some(nft) = self.non_fungible_token.nfts.get(&token_id).await.unwrap() else {
+                continue;
+            };
+            if nft.status != NftStatus::OnSale {
+                continue;
+            }
+            let payload = {
+                let mut runtime = self
+                    .runtime
+                    .try_lock()
+                    .expect("Services only run in a single thread");
+                runtime.read_data_blob(nft.blob_hash)
+            };
+            let nft_output = NftOutput::new(nft, payload);
+            result.insert(nft_output.token_id.clone(), nft_output);
+        }
+
+        result
+    }
+}
+
+struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn mint(
+        &self,
+        minter: AccountOwner,
+        name: String,
+        blob_hash: DataBlobHash,
+        token: String,        // ETH, SOL
+        price: String,        // 0.05 [token]
+        id: u64,              // specific chain nft id
+        chain_minter: String, // chain nft minter
+        chain_owner: String,  // chain nft owner
+        description: String,
+        collection: Option<String>,
+        royalty_bps: Option<u16>,
+        revealable: Option<bool>,
+        list_immediately: Option<bool>,
+        soulbound: Option<bool>,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Mint {
+            minter,
+            name,
+            blob_hash,
+            token,
+            price,
+            id,
+            chain_owner,
+            chain_minter,
+            description,
+            collection: collection.unwrap_or_default(),
+            royalty_bps,
+            revealable: revealable.unwrap_or_default(),
+            list_immediately: list_immediately.unwrap_or(true),
+            soulbound: soulbound.unwrap_or_default(),
+        })?)
+    }
+
+    async fn transfer(
+        &self,
+        source_owner: AccountOwner,
+        token_id: String,
+        target_account: Account,
+        chain_owner: Option<String>,
+        buy_from_token: String,
+        to_token: String,
+        amount: String,
+        memo: Option<String>,
+        royalty_bps_override: Option<u16>,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Transfer {
+            source_owner,
+            token_id: decode_token_id(&token_id)?,
+            target_account,
+            chain_owner,
+            buy_from_token,
+            to_token,
+            amount,
+            memo,
+            royalty_bps_override,
+        })?)
+    }
+
+    async fn buy(&self, token_id: String, payment: Account) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Buy {
+            token_id: decode_token_id(&token_id)?,
+            payment,
+        })?)
+    }
+
+    async fn claim(
+        &self,
+        source_account: Account,
+        token_id: String,
+        target_account: Account,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Claim {
+            source_account,
+            token_id: decode_token_id(&token_id)?,
+            target_account,
+        })?)
+    }
+
+    async fn batch_claim(
+        &self,
+        source_account: Account,
+        token_ids: Vec<String>,
+        target_account: Account,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::BatchClaim {
+            source_account,
+            token_ids: token_ids
+                .iter()
+                .map(|token_id| decode_token_id(token_id))
+                .collect::<Result<_>>()?,
+            target_account,
+        })?)
+    }
+
+    async fn safe_transfer(
+        &self,
+        source_owner: AccountOwner,
+        token_id: String,
+        target_account: Account,
+        memo: Option<String>,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SafeTransfer {
+            source_owner,
+            token_id: decode_token_id(&token_id)?,
+            target_account,
+            memo,
+        })?)
+    }
+
+    async fn transfer_all_to_owner(
+        &self,
+        source_owner: AccountOwner,
+        target: AccountOwner,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::TransferAllToOwner {
+            source_owner,
+            target,
+        })?)
+    }
+
+    async fn merge_owner(&self, from: AccountOwner, to: AccountOwner) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::MergeOwner { from, to })?)
+    }
+
+    async fn set_status_for_owned(
+        &self,
+        source_owner: AccountOwner,
+        status: NftStatus,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SetStatusForOwned {
+            source_owner,
+            status,
+        })?)
+    }
+
+    async fn adjust_prices(&self, source_owner: AccountOwner, factor_bps: u32) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::AdjustPrices {
+            source_owner,
+            factor_bps,
+        })?)
+    }
+
+    async fn reclaim_stuck_transfer(&self, token_id: String) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::ReclaimStuckTransfer {
+            token_id: decode_token_id(&token_id)?,
+        })?)
+    }
+
+    async fn reveal(
+        &self,
+        token_id: String,
+        new_blob_hash: DataBlobHash,
+        new_name: String,
+        new_description: String,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Reveal {
+            token_id: decode_token_id(&token_id)?,
+            new_blob_hash,
+            new_name,
+            new_description,
+        })?)
+    }
+
+    async fn update_external_id(&self, token_id: String, new_id: u64) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::UpdateExternalId {
+            token_id: decode_token_id(&token_id)?,
+            new_id,
+        })?)
+    }
+
+    async fn set_chain_owner(&self, token_id: String, chain_owner: String) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SetChainOwner {
+            token_id: decode_token_id(&token_id)?,
+            chain_owner,
+        })?)
+    }
+
+    async fn set_minter(&self, token_id: String, new_minter: AccountOwner) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SetMinter {
+            token_id: decode_token_id(&token_id)?,
+            new_minter,
+        })?)
+    }
+
+    async fn set_collection_royalty(
+        &self,
+        collection: String,
+        royalty_bps: u16,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SetCollectionRoyalty {
+            collection,
+            royalty_bps,
+        })?)
+    }
+
+    async fn set_collection_meta(
+        &self,
+        collection: String,
+        name: String,
+        description: String,
+        banner_blob_hash: Option<DataBlobHash>,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SetCollectionMeta {
+            collection,
+            name,
+            description,
+            banner_blob_hash,
+        })?)
+    }
+
+    async fn reindex_all(&self) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::ReindexAll)?)
+    }
+
+    async fn burn(&self, token_id: String) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Burn {
+            token_id: decode_token_id(&token_id)?,
+        })?)
+    }
+
+    async fn set_listing_deadline(
+        &self,
+        token_id: String,
+        expires_at: Timestamp,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::SetListingDeadline {
+            token_id: decode_token_id(&token_id)?,
+            expires_at,
+        })?)
+    }
+
+    async fn cancel_listing(&self, token_id: String) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::CancelListing {
+            token_id: decode_token_id(&token_id)?,
+        })?)
+    }
+
+    async fn flag(&self, token_id: String, reason: String) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Flag {
+            token_id: decode_token_id(&token_id)?,
+            reason,
+        })?)
+    }
+
+    async fn moderate(&self, token_id: String, action: ModerationAction) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Moderate {
+            token_id: decode_token_id(&token_id)?,
+            action,
+        })?)
+    }
+
+    async fn relist(&self, token_id: String, price: String, token: String) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::Relist {
+            token_id: decode_token_id(&token_id)?,
+            price,
+            token,
+        })?)
+    }
+
+    async fn redeem_voucher(&self, voucher: MintVoucher, signature: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::RedeemVoucher {
+            voucher,
+            signature,
+        })?)
+    }
+
+    async fn listNftForSale(
+        &self,
         token_id: String,
         chain_owner: String,
-    ) -> Vec<u8> {
-        bcs::to_bytes(&Operation::ListNftForSale {
-            token_id: TokenId {
-                id: STANDARD_NO_PAD.decode(token_id).unwrap(),
-            },
+        accepted_currencies: Option<BTreeSet<String>>,
+        listings: Option<Vec<Listing>>,
+        expires_at: Option<Timestamp>,
+    ) -> Result<Vec<u8>> {
+        Ok(bcs::to_bytes(&Operation::ListNftForSale {
+            token_id: decode_token_id(&token_id)?,
             chain_owner,
-        }).unwrap()
+            accepted_currencies,
+            listings,
+            expires_at,
+        })?)
+    }
+}
+
+/// Decodes a base64 token ID as used by the GraphQL API, reporting malformed input as a
+/// GraphQL error.
+fn decode_token_id(token_id: &str) -> Result<TokenId> {
+    let id = STANDARD_NO_PAD.decode(token_id).map_err(|error| {
+        async_graphql::Error::new(format!("Invalid token ID {token_id:?}: {error}"))
+    })?;
+    Ok(TokenId { id })
+}
+
+/// Decodes a hex token ID, reporting odd-length or otherwise malformed input as a GraphQL
+/// error.
+fn decode_hex_token_id(token_id: &str) -> Result<TokenId> {
+    let id = hex::decode(token_id).map_err(|error| {
+        async_graphql::Error::new(format!("Invalid hex token ID {token_id:?}: {error}"))
+    })?;
+    Ok(TokenId { id })
+}
+
+/// Returns whether an NFT may be shown by public listing queries, i.e. hasn't been hidden by
+/// moderation. Hidden NFTs remain available through `nftRaw` and `exportNfts`, and through
+/// lookups by token or external ID, for administration and indexing.
+fn is_publicly_visible(nft: &Nft) -> bool {
+    nft.status != NftStatus::Hidden
+}
+
+/// Advances the SplitMix64 generator `state` and returns its next pseudo-random value.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::splitmix64;
+
+    #[test]
+    fn splitmix64_matches_reference_sequence() {
+        let mut state = 0;
+        assert_eq!(splitmix64(&mut state), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(splitmix64(&mut state), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(splitmix64(&mut state), 0x06c4_5d18_8009_454f);
     }
 }
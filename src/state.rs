@@ -4,19 +4,69 @@
 use std::collections::BTreeSet;
 
 use async_graphql::SimpleObject;
-use linera_sdk::{base::AccountOwner, views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext}, DataBlobHash};
-use non_fungible::{Nft, TokenId};
+use linera_sdk::{
+    base::{AccountOwner, Amount, Timestamp},
+    views::{linera_views, LogView, MapView, RegisterView, RootView, ViewStorageContext},
+    DataBlobHash,
+};
+use non_fungible::{
+    CollectionCurrency, CollectionMeta, Config, Flag, Listing, MarketEvent, Nft, NftStatus,
+    OutboundTransfer, PriceStats, TokenId,
+};
 
 /// The application state.
 #[derive(RootView, SimpleObject)]
 #[view(context = "ViewStorageContext")]
 pub struct NonFungibleTokenState {
+    // The configuration provided at instantiation
+    pub config: RegisterView<Config>,
     // Map from token ID to the NFT data
     pub nfts: MapView<TokenId, Nft>,
     // Map from owners to the set of NFT token IDs they own
     pub owned_token_ids: MapView<AccountOwner, BTreeSet<TokenId>>,
     // chain owned to the set of NFTs for multiple chains
     pub blob_token_ids: MapView<u64, TokenId>,
+    // Map from collection names to the set of NFT token IDs they contain
+    pub collection_token_ids: MapView<String, BTreeSet<TokenId>>,
+    // Map from collection to the royalty, in basis points, of NFTs minted without one
+    pub collection_royalty_bps: MapView<String, u16>,
+    // Map from collection names to their display metadata
+    pub collection_metadata: MapView<String, CollectionMeta>,
+    // Map from external chain owner to the token IDs they own there
+    pub chain_owner_token_ids: MapView<String, BTreeSet<TokenId>>,
+    // Map from minter to the token IDs they minted
+    pub minter_token_ids: MapView<AccountOwner, BTreeSet<TokenId>>,
+    // Map from status to the set of NFT token IDs with that status
+    pub status_token_ids: MapView<NftStatus, BTreeSet<TokenId>>,
+    // Map from token ID to the alternative prices of a listed NFT, if it has several
+    pub listings: MapView<TokenId, Vec<Listing>>,
+    // Map from token ID to the last time the NFT was put on sale
+    pub last_listed_at: MapView<TokenId, Timestamp>,
+    // Map from token ID to the time its current listing expires, for time-limited listings
+    pub listing_expiries: MapView<TokenId, Timestamp>,
+    // Map from token ID to the cross-chain transfers awaiting acknowledgment
+    pub outbound_transfers: MapView<TokenId, OutboundTransfer>,
+    // Map from token ID to the time this chain claimed it from another chain, until it arrives
+    pub pending_in: MapView<TokenId, Timestamp>,
+    // Map from currency to the statistics of completed sales in that currency
+    pub price_stats: MapView<String, PriceStats>,
+    // Map from collection and currency to the total price of completed sales
+    pub collection_volumes: MapView<CollectionCurrency, Amount>,
+    // Map from seller to the total price of their completed sales, across currencies
+    pub seller_volume: MapView<AccountOwner, Amount>,
+    // Map from buyer to the total price of their completed purchases, across currencies
+    pub buyer_volume: MapView<AccountOwner, Amount>,
+    // Map from minter to the nonces of their mint vouchers that have been redeemed
+    pub redeemed_vouchers: MapView<AccountOwner, BTreeSet<u64>>,
     // Counter of NFTs minted in this chain, used for hash uniqueness
     pub num_minted_nfts: RegisterView<u64>,
+    // Counter of NFTs burned in this chain
+    pub num_burned: RegisterView<u64>,
+    // Map from token ID to the moderation reports filed against it
+    pub flags: MapView<TokenId, Vec<Flag>>,
+    // Log of marketplace events, in the order they happened
+    pub events: LogView<MarketEvent>,
+    // Map from owner to the sequence numbers of the events they took part in, as minter,
+    // seller or buyer
+    pub owner_events: MapView<AccountOwner, Vec<u64>>,
 }
@@ -0,0 +1,1170 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Integration tests for the Non-Fungible Token application on a single chain.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use async_graphql::{Request, Variables};
+use base64::engine::{general_purpose::STANDARD_NO_PAD, Engine as _};
+use fungible::Account;
+use linera_execution::system::SystemOperation;
+use linera_sdk::{
+    base::{crypto::Signature, AccountOwner, Amount, ApplicationId, Blob, CryptoHash, Owner},
+    test::{ActiveChain, TestValidator},
+    DataBlobHash,
+};
+use non_fungible::{
+    BurnPolicy, Config, Listing, MintVoucher, ModerationAction, NonFungibleTokenAbi, Operation,
+    Parameters, TokenId,
+};
+use serde_json::json;
+use universal_solver::UniversalSolverAbi;
+
+/// Minting to three owners and moving away all the NFTs of one of them leaves two holders.
+#[tokio::test(flavor = "multi_thread")]
+async fn holder_count_ignores_owners_without_nfts() {
+    let market = Marketplace::new(Config::default()).await;
+
+    for (seed, name) in [(1, "first"), (2, "second"), (3, "third")] {
+        market.mint(MintArgs::new(owner(seed), name)).await;
+    }
+    let token_id = market.token_id_by_name("third").await;
+    market
+        .execute(Operation::Claim {
+            source_account: market.account(owner(3)),
+            token_id,
+            target_account: market.account(owner(1)),
+        })
+        .await;
+
+    let response = market.query("query { holderCount }", json!({})).await;
+    assert_eq!(response["holderCount"], 2);
+}
+
+/// Names and descriptions can be as long as the configured limits, but not longer.
+#[tokio::test(flavor = "multi_thread")]
+async fn metadata_limits_are_inclusive() {
+    let config = Config {
+        max_name_len: 8,
+        max_description_len: 16,
+        ..Config::default()
+    };
+    let market = Marketplace::new(config).await;
+
+    market
+        .mint(MintArgs {
+            description: "0123456789abcdef",
+            ..MintArgs::new(owner(1), "12345678")
+        })
+        .await;
+
+    assert!(
+        market
+            .rejects(market.mint_operation(MintArgs::new(owner(1), "123456789")))
+            .await,
+        "Minting with a name one character too long must fail"
+    );
+    assert!(
+        market
+            .rejects(market.mint_operation(MintArgs {
+                description: "0123456789abcdefg",
+                ..MintArgs::new(owner(1), "too long")
+            }))
+            .await,
+        "Minting with a description one character too long must fail"
+    );
+}
+
+/// Batch claims can move up to the configured number of NFTs, but not more.
+#[tokio::test(flavor = "multi_thread")]
+async fn batch_claims_are_capped() {
+    let config = Config {
+        max_batch_size: 2,
+        ..Config::default()
+    };
+    let market = Marketplace::new(config).await;
+    let signer = market.signer();
+
+    let mut token_ids = Vec::new();
+    for name in ["first", "second", "third"] {
+        token_ids.push(market.mint(MintArgs::new(signer, name)).await);
+    }
+
+    let batch_claim = |token_ids: &[TokenId]| Operation::BatchClaim {
+        source_account: market.account(signer),
+        token_ids: token_ids.to_vec(),
+        target_account: market.account(owner(1)),
+    };
+    assert!(
+        market.rejects(batch_claim(&token_ids)).await,
+        "A batch one token over the limit must fail"
+    );
+
+    market.execute(batch_claim(&token_ids[..2])).await;
+
+    let response = market.query("query { holderCount }", json!({})).await;
+    assert_eq!(response["holderCount"], 2);
+}
+
+/// Soulbound NFTs can't be claimed by another account, but their owner can burn them.
+#[tokio::test(flavor = "multi_thread")]
+async fn soulbound_nfts_can_be_burned_but_not_transferred() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+
+    let token_id = market
+        .mint(MintArgs {
+            soulbound: true,
+            ..MintArgs::new(signer, "badge")
+        })
+        .await;
+
+    assert!(
+        market
+            .rejects(Operation::Claim {
+                source_account: market.account(signer),
+                token_id: token_id.clone(),
+                target_account: market.account(owner(1)),
+            })
+            .await,
+        "Claiming a soulbound NFT must fail"
+    );
+
+    market.execute(Operation::Burn { token_id }).await;
+
+    let response = market.query("query { holderCount }", json!({})).await;
+    assert_eq!(response["holderCount"], 0);
+}
+
+/// A voucher signed by the minter mints once, and can't be redeemed a second time.
+#[tokio::test(flavor = "multi_thread")]
+async fn vouchers_cannot_be_replayed() {
+    let market = Marketplace::new(Config::default()).await;
+
+    let voucher = MintVoucher {
+        chain_id: market.chain.id(),
+        application_id: market.application_id.forget_abi(),
+        minter_public_key: market.chain.public_key(),
+        nonce: 7,
+        name: "lazy".to_owned(),
+        blob_hash: market.blob_hash,
+        token: "ETH".to_owned(),
+        price: "0.05".to_owned(),
+        id: 1,
+        chain_minter: String::new(),
+        chain_owner: String::new(),
+        description: String::new(),
+        collection: String::new(),
+        royalty_bps: None,
+        revealable: false,
+        soulbound: false,
+    };
+    let signature = bcs::to_bytes(&Signature::new(&voucher, market.chain.key_pair()))
+        .expect("Failed to serialize signature");
+    let redeem = || Operation::RedeemVoucher {
+        voucher: voucher.clone(),
+        signature: signature.clone(),
+    };
+
+    market.execute(redeem()).await;
+    market.token_id_by_name("lazy").await;
+
+    assert!(
+        market.rejects(redeem()).await,
+        "Redeeming a voucher twice must fail"
+    );
+}
+
+/// A sale through `Transfer` asks the deployed solver to swap the payment to the seller's
+/// external address, and hands the NFT over to the buyer.
+#[tokio::test(flavor = "multi_thread")]
+async fn transfer_swaps_payment_through_the_solver() {
+    let market = Marketplace::new(Config::default()).await;
+    let token_id = market
+        .mint(MintArgs {
+            price: "1",
+            chain_owner: "0xseller",
+            list_immediately: true,
+            ..MintArgs::new(owner(1), "for sale")
+        })
+        .await;
+
+    market.execute(market.transfer(&token_id, "1", "ETH")).await;
+
+    assert_eq!(market.swaps().await, [(Amount::ONE, "0xseller".to_owned())]);
+    let nft = market.nft(&token_id).await;
+    assert_eq!(nft["owner"], json!(owner(2)));
+    assert_eq!(nft["status"], "SOLD");
+}
+
+/// The floor price of a collection is its lowest price on sale in the currency, and there is
+/// none if nothing is on sale in that currency.
+#[tokio::test(flavor = "multi_thread")]
+async fn floor_price_is_the_lowest_listing_in_the_currency() {
+    let market = Marketplace::new(Config::default()).await;
+    for (name, token, price, list_immediately) in [
+        ("third", "ETH", "3", true),
+        ("first", "ETH", "1", true),
+        ("second", "ETH", "2", true),
+        ("unlisted", "ETH", "0.5", false),
+        ("in SOL", "SOL", "0.1", true),
+    ] {
+        market
+            .mint(MintArgs {
+                token,
+                price,
+                collection: "art",
+                list_immediately,
+                ..MintArgs::new(owner(1), name)
+            })
+            .await;
+    }
+
+    let floor_price = |currency: &str| {
+        market.query(
+            "query($currency: String!) { floorPrice(collection: \"art\", currency: $currency) }",
+            json!({ "currency": currency }),
+        )
+    };
+    assert_eq!(amount(&floor_price("ETH").await["floorPrice"]), Amount::ONE);
+    assert!(floor_price("USDC").await["floorPrice"].is_null());
+}
+
+/// Only the NFTs of an owner that are on sale are returned by `ownedOnSale`.
+#[tokio::test(flavor = "multi_thread")]
+async fn owned_on_sale_only_returns_the_owners_listed_nfts() {
+    let market = Marketplace::new(Config::default()).await;
+    let listed = market
+        .mint(MintArgs {
+            list_immediately: true,
+            ..MintArgs::new(owner(1), "listed")
+        })
+        .await;
+    market.mint(MintArgs::new(owner(1), "unlisted")).await;
+    market
+        .mint(MintArgs {
+            list_immediately: true,
+            ..MintArgs::new(owner(2), "someone else's")
+        })
+        .await;
+
+    let response = market
+        .query(
+            "query($owner: AccountOwner!) { ownedOnSale(owner: $owner) }",
+            json!({ "owner": owner(1) }),
+        )
+        .await;
+    assert_eq!(token_ids(&response["ownedOnSale"]), [encode(&listed)]);
+}
+
+/// `blobHashOf` returns the hex hash of the minted blob, and nothing for unknown tokens.
+#[tokio::test(flavor = "multi_thread")]
+async fn blob_hash_of_returns_the_minted_blob_hash() {
+    let market = Marketplace::new(Config::default()).await;
+    let token_id = market.mint(MintArgs::new(owner(1), "image")).await;
+    let unknown = TokenId { id: vec![0; 32] };
+
+    let blob_hash_of = |token_id: &TokenId| {
+        market.query(
+            "query($tokenId: String!) { blobHashOf(tokenId: $tokenId) }",
+            json!({ "tokenId": encode(token_id) }),
+        )
+    };
+    assert_eq!(
+        blob_hash_of(&token_id).await["blobHashOf"],
+        market.blob_hash.0.to_string()
+    );
+    assert!(blob_hash_of(&unknown).await["blobHashOf"].is_null());
+}
+
+/// An NFT listed for some currencies only can't be bought in the others, even at a listed
+/// price, and is bought at its listing in an accepted one.
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_respect_accepted_currencies() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+    let token_id = market.mint(MintArgs::new(signer, "SOL only")).await;
+    market
+        .execute(Operation::ListNftForSale {
+            token_id: token_id.clone(),
+            chain_owner: "0xseller".to_owned(),
+            accepted_currencies: Some(["SOL".to_owned()].into()),
+            listings: Some(vec![
+                Listing {
+                    price: "1".to_owned(),
+                    currency: "ETH".to_owned(),
+                },
+                Listing {
+                    price: "10".to_owned(),
+                    currency: "SOL".to_owned(),
+                },
+            ]),
+            expires_at: None,
+        })
+        .await;
+
+    assert!(
+        market.rejects(market.transfer(&token_id, "1", "ETH")).await,
+        "Buying in a currency that isn't accepted must fail"
+    );
+
+    market
+        .execute(market.transfer(&token_id, "10", "SOL"))
+        .await;
+    assert_eq!(
+        market.swaps().await,
+        [(Amount::from_tokens(10), "0xseller".to_owned())]
+    );
+    assert_eq!(market.nft(&token_id).await["owner"], json!(owner(2)));
+}
+
+/// Minting three NFTs and burning one leaves two active out of three ever minted.
+#[tokio::test(flavor = "multi_thread")]
+async fn supply_stats_count_minted_active_and_burned_nfts() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+    let mut token_ids = Vec::new();
+    for name in ["first", "second", "third"] {
+        token_ids.push(market.mint(MintArgs::new(signer, name)).await);
+    }
+
+    market
+        .execute(Operation::Burn {
+            token_id: token_ids[0].clone(),
+        })
+        .await;
+
+    assert_eq!(
+        market.supply_stats().await,
+        json!({ "everMinted": 3, "currentlyActive": 2, "burned": 1 })
+    );
+}
+
+/// Moving an NFT to another owner doesn't count as a burn, while burning it does.
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_are_not_counted_as_burns() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+    let token_id = market.mint(MintArgs::new(owner(1), "gift")).await;
+
+    market
+        .execute(Operation::Claim {
+            source_account: market.account(owner(1)),
+            token_id: token_id.clone(),
+            target_account: market.account(signer),
+        })
+        .await;
+    assert_eq!(
+        market.supply_stats().await,
+        json!({ "everMinted": 1, "currentlyActive": 1, "burned": 0 })
+    );
+
+    market.execute(Operation::Burn { token_id }).await;
+    assert_eq!(
+        market.supply_stats().await,
+        json!({ "everMinted": 1, "currentlyActive": 0, "burned": 1 })
+    );
+}
+
+/// A sold NFT can't be bought again until its new owner relists it at a new price.
+#[tokio::test(flavor = "multi_thread")]
+async fn sold_nfts_can_be_relisted() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+    let token_id = market.mint(MintArgs::new(owner(1), "resold")).await;
+    market
+        .execute(Operation::Claim {
+            source_account: market.account(owner(1)),
+            token_id: token_id.clone(),
+            target_account: market.account(signer),
+        })
+        .await;
+    assert_eq!(market.nft(&token_id).await["status"], "SOLD");
+    assert!(
+        market.rejects(market.transfer(&token_id, "2", "SOL")).await,
+        "Buying a sold NFT must fail"
+    );
+
+    market
+        .execute(Operation::Relist {
+            token_id: token_id.clone(),
+            price: "2".to_owned(),
+            token: "SOL".to_owned(),
+        })
+        .await;
+    let nft = market.nft(&token_id).await;
+    assert_eq!(
+        (&nft["status"], &nft["price"], &nft["token"]),
+        (&json!("ON_SALE"), &json!("2"), &json!("SOL"))
+    );
+
+    market.execute(market.transfer(&token_id, "2", "SOL")).await;
+    assert_eq!(market.nft(&token_id).await["owner"], json!(owner(2)));
+}
+
+/// `TransferAllToOwner` moves every NFT of the source owner, leaving it with none.
+#[tokio::test(flavor = "multi_thread")]
+async fn transfer_all_to_owner_moves_every_nft() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+    for name in ["first", "second", "third"] {
+        market.mint(MintArgs::new(signer, name)).await;
+    }
+
+    market
+        .execute(Operation::TransferAllToOwner {
+            source_owner: signer,
+            target: owner(1),
+        })
+        .await;
+
+    assert_eq!(market.owned_token_count(signer).await, 0);
+    assert_eq!(market.owned_token_count(owner(1)).await, 3);
+}
+
+/// Price statistics track the lowest, highest and last sale prices and the number of sales in
+/// each currency.
+#[tokio::test(flavor = "multi_thread")]
+async fn price_stats_track_sales_per_currency() {
+    let market = Marketplace::new(Config::default()).await;
+    for (name, price) in [("first", "1"), ("second", "3"), ("third", "2")] {
+        let token_id = market
+            .mint(MintArgs {
+                price,
+                list_immediately: true,
+                ..MintArgs::new(owner(1), name)
+            })
+            .await;
+        market
+            .execute(market.transfer(&token_id, price, "ETH"))
+            .await;
+    }
+
+    let price_stats = |currency: &str| {
+        market.query(
+            "query($currency: String!) { \
+                priceStats(currency: $currency) { minSale maxSale lastSale count } \
+            }",
+            json!({ "currency": currency }),
+        )
+    };
+    let response = price_stats("ETH").await;
+    let stats = &response["priceStats"];
+    assert_eq!(
+        [&stats["minSale"], &stats["maxSale"], &stats["lastSale"]].map(amount),
+        [1, 3, 2].map(Amount::from_tokens)
+    );
+    assert_eq!(stats["count"], 3);
+    assert!(price_stats("SOL").await["priceStats"].is_null());
+}
+
+/// NFTs can be minted with a royalty up to the configured cap, but not above it.
+#[tokio::test(flavor = "multi_thread")]
+async fn mint_royalties_are_capped() {
+    let config = Config {
+        max_royalty_bps: 1_000,
+        ..Config::default()
+    };
+    let market = Marketplace::new(config).await;
+
+    let token_id = market
+        .mint(MintArgs {
+            royalty_bps: Some(1_000),
+            ..MintArgs::new(owner(1), "at the cap")
+        })
+        .await;
+    let response = market
+        .query(
+            "query($tokenId: String!) { nft(tokenId: $tokenId) { royaltyBps } }",
+            json!({ "tokenId": encode(&token_id) }),
+        )
+        .await;
+    assert_eq!(response["nft"]["royaltyBps"], 1_000);
+
+    assert!(
+        market
+            .rejects(market.mint_operation(MintArgs {
+                royalty_bps: Some(1_001),
+                ..MintArgs::new(owner(1), "above the cap")
+            }))
+            .await,
+        "Minting with a royalty above the cap must fail"
+    );
+}
+
+/// Selling one NFT of a three-item collection counts the buyer as an owner, takes the sold
+/// NFT out of the floor price and adds its price to the volume.
+#[tokio::test(flavor = "multi_thread")]
+async fn collection_stats_reflect_sales() {
+    let market = Marketplace::new(Config::default()).await;
+    let mut token_ids = Vec::new();
+    for (seed, name, price) in [(1, "first", "2"), (1, "second", "3"), (3, "third", "4")] {
+        let token_id = market
+            .mint(MintArgs {
+                price,
+                collection: "art",
+                list_immediately: true,
+                ..MintArgs::new(owner(seed), name)
+            })
+            .await;
+        token_ids.push(token_id);
+    }
+
+    market
+        .execute(market.transfer(&token_ids[0], "2", "ETH"))
+        .await;
+
+    let response = market
+        .query(
+            "query { \
+                collectionStats(collection: \"art\", currency: \"ETH\") { \
+                    itemCount ownerCount floorPrice totalVolume \
+                } \
+            }",
+            json!({}),
+        )
+        .await;
+    let stats = &response["collectionStats"];
+    assert_eq!(
+        (&stats["itemCount"], &stats["ownerCount"]),
+        (&json!(3), &json!(3))
+    );
+    assert_eq!(amount(&stats["floorPrice"]), Amount::from_tokens(3));
+    assert_eq!(amount(&stats["totalVolume"]), Amount::from_tokens(2));
+}
+
+/// The minter of an NFT held by someone else may only burn it under the `OwnerOrMinter`
+/// policy.
+#[tokio::test(flavor = "multi_thread")]
+async fn minters_can_only_burn_under_owner_or_minter_policy() {
+    for (burn_policy, minter_can_burn) in [
+        (BurnPolicy::OwnerOnly, false),
+        (BurnPolicy::OwnerOrMinter, true),
+    ] {
+        let config = Config {
+            burn_policy,
+            ..Config::default()
+        };
+        let market = Marketplace::new(config).await;
+        let signer = market.signer();
+        let token_id = market.mint(MintArgs::new(signer, "redeemable")).await;
+        market
+            .execute(Operation::Claim {
+                source_account: market.account(signer),
+                token_id: token_id.clone(),
+                target_account: market.account(owner(1)),
+            })
+            .await;
+
+        let rejected = market.rejects(Operation::Burn { token_id }).await;
+        assert_eq!(
+            rejected, !minter_can_burn,
+            "Unexpected outcome of a minter burn under {burn_policy:?}"
+        );
+    }
+}
+
+/// Merging an owner with two NFTs into one with a single NFT leaves the latter with three.
+#[tokio::test(flavor = "multi_thread")]
+async fn merge_owner_combines_holdings() {
+    let market = Marketplace::new(Config::default()).await;
+    let signer = market.signer();
+    for name in ["old key 1", "old key 2"] {
+        market.mint(MintArgs::new(signer, name)).await;
+    }
+    market.mint(MintArgs::new(owner(1), "new key")).await;
+
+    market
+        .execute(Operation::MergeOwner {
+            from: signer,
+            to: owner(1),
+        })
+        .await;
+
+    assert_eq!(market.owned_token_count(signer).await, 0);
+    assert_eq!(market.owned_token_count(owner(1)).await, 3);
+    let response = market.query("query { holderCount }", json!({})).await;
+    assert_eq!(response["holderCount"], 1);
+}
+
+/// `SetChainOwner` moves the NFT from the old external owner's index entry to the new one's.
+#[tokio::test(flavor = "multi_thread")]
+async fn set_chain_owner_moves_the_index_entry() {
+    let market = Marketplace::new(Config::default()).await;
+    let token_id = market
+        .mint(MintArgs {
+            chain_owner: "0xold",
+            ..MintArgs::new(market.signer(), "rotated")
+        })
+        .await;
+
+    market
+        .execute(Operation::SetChainOwner {
+            token_id: token_id.clone(),
+            chain_owner: "0xnew".to_owned(),
+        })
+        .await;
+
+    let token_ids_of = |chain_owner: &str| {
+        market.query(
+            "query($chainOwner: String!) { tokenIdsByChainOwner(chainOwner: $chainOwner) }",
+            json!({ "chainOwner": chain_owner }),
+        )
+    };
+    assert_eq!(
+        token_ids_of("0xold").await["tokenIdsByChainOwner"],
+        json!([])
+    );
+    assert_eq!(
+        token_ids_of("0xnew").await["tokenIdsByChainOwner"],
+        json!([encode(&token_id)])
+    );
+    assert_eq!(market.nft(&token_id).await["chainOwner"], "0xnew");
+}
+
+/// Sellers are ranked by the total price of their sales, highest first.
+#[tokio::test(flavor = "multi_thread")]
+async fn top_sellers_are_ranked_by_volume() {
+    let market = Marketplace::new(Config::default()).await;
+    for (seed, name, price) in [(1, "first", "1"), (1, "second", "2"), (3, "third", "5")] {
+        let token_id = market
+            .mint(MintArgs {
+                price,
+                list_immediately: true,
+                ..MintArgs::new(owner(seed), name)
+            })
+            .await;
+        market
+            .execute(market.transfer(&token_id, price, "ETH"))
+            .await;
+    }
+
+    let response = market
+        .query(
+            "query { topSellers(limit: 2) { account volume } }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(
+        account_volumes(&response["topSellers"]),
+        [
+            (json!(owner(3)), Amount::from_tokens(5)),
+            (json!(owner(1)), Amount::from_tokens(3)),
+        ]
+    );
+}
+
+/// A buyer's volume adds up the prices of all their purchases.
+#[tokio::test(flavor = "multi_thread")]
+async fn buyer_volume_accumulates_across_purchases() {
+    let market = Marketplace::new(Config::default()).await;
+    for (name, price) in [("first", "1"), ("second", "2")] {
+        let token_id = market
+            .mint(MintArgs {
+                price,
+                list_immediately: true,
+                ..MintArgs::new(owner(1), name)
+            })
+            .await;
+        market
+            .execute(market.transfer(&token_id, price, "ETH"))
+            .await;
+    }
+
+    let response = market
+        .query(
+            "query { topBuyers(limit: 10) { account volume } }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(
+        account_volumes(&response["topBuyers"]),
+        [(json!(owner(2)), Amount::from_tokens(3))]
+    );
+}
+
+/// Reports are recorded per NFT, and the administrator can take a reported NFT off sale or
+/// dismiss its reports.
+#[tokio::test(flavor = "multi_thread")]
+async fn flagged_nfts_can_be_hidden_and_their_flags_cleared() {
+    let market = Marketplace::with_config(|admin| Config {
+        admin: Some(admin),
+        ..Config::default()
+    })
+    .await;
+    let token_id = market
+        .mint(MintArgs {
+            list_immediately: true,
+            ..MintArgs::new(owner(1), "reported")
+        })
+        .await;
+
+    market
+        .execute(Operation::Flag {
+            token_id: token_id.clone(),
+            reason: "stolen art".to_owned(),
+        })
+        .await;
+    let flags = market.flags(&token_id).await;
+    assert_eq!(
+        flags,
+        json!([{ "reporter": market.signer(), "reason": "stolen art" }])
+    );
+
+    market
+        .execute(Operation::Moderate {
+            token_id: token_id.clone(),
+            action: ModerationAction::Hide,
+        })
+        .await;
+    assert_eq!(market.nft(&token_id).await["status"], "HIDDEN");
+    let response = market
+        .query(
+            "query { nftsByCurrencyAndStatus(currency: \"ETH\", status: ON_SALE) }",
+            json!({}),
+        )
+        .await;
+    assert!(token_ids(&response["nftsByCurrencyAndStatus"]).is_empty());
+
+    market
+        .execute(Operation::Moderate {
+            token_id: token_id.clone(),
+            action: ModerationAction::ClearFlags,
+        })
+        .await;
+    assert_eq!(market.flags(&token_id).await, json!([]));
+}
+
+/// A hidden NFT disappears from public listing queries, but stays available as stored.
+#[tokio::test(flavor = "multi_thread")]
+async fn hidden_nfts_are_excluded_from_public_queries() {
+    let market = Marketplace::with_config(|admin| Config {
+        admin: Some(admin),
+        ..Config::default()
+    })
+    .await;
+    let visible = market.mint(MintArgs::new(owner(1), "visible")).await;
+    let hidden = market.mint(MintArgs::new(owner(1), "hidden")).await;
+    market
+        .execute(Operation::Moderate {
+            token_id: hidden.clone(),
+            action: ModerationAction::Hide,
+        })
+        .await;
+
+    let response = market
+        .query(
+            "query { \
+                nfts \
+                recentlyActive(limit: 10) { tokenId } \
+                nftsByCurrencyAndStatus(currency: \"ETH\", status: HIDDEN) \
+            }",
+            json!({}),
+        )
+        .await;
+    assert_eq!(token_ids(&response["nfts"]), [encode(&visible)]);
+    assert_eq!(
+        response["recentlyActive"],
+        json!([{ "tokenId": encode(&visible) }])
+    );
+    assert!(token_ids(&response["nftsByCurrencyAndStatus"]).is_empty());
+
+    let response = market
+        .query(
+            "query($tokenId: String!) { nftRaw(tokenId: $tokenId) { status } }",
+            json!({ "tokenId": encode(&hidden) }),
+        )
+        .await;
+    assert_eq!(response["nftRaw"]["status"], "HIDDEN");
+}
+
+/// Two owners holding one NFT each and one holding three make the histogram `{1: 2, 3: 1}`.
+#[tokio::test(flavor = "multi_thread")]
+async fn ownership_distribution_counts_owners_per_holding() {
+    let market = Marketplace::new(Config::default()).await;
+    for (seed, name) in [
+        (1, "first"),
+        (2, "second"),
+        (3, "third"),
+        (3, "fourth"),
+        (3, "fifth"),
+    ] {
+        market.mint(MintArgs::new(owner(seed), name)).await;
+    }
+
+    let response = market
+        .query("query { ownershipDistribution }", json!({}))
+        .await;
+    assert_eq!(response["ownershipDistribution"], json!({ "1": 2, "3": 1 }));
+}
+
+/// `nftByHexId` finds an NFT from its hex token ID, and reports malformed IDs as errors.
+#[tokio::test(flavor = "multi_thread")]
+async fn nft_by_hex_id_decodes_hex_token_ids() {
+    let market = Marketplace::new(Config::default()).await;
+    let token_id = market.mint(MintArgs::new(owner(1), "hex")).await;
+    let query = "query($hex: String!) { nftByHexId(hex: $hex) { tokenId name } }";
+
+    let response = market
+        .query(query, json!({ "hex": hex::encode(&token_id.id) }))
+        .await;
+    assert_eq!(
+        response["nftByHexId"],
+        json!({ "tokenId": encode(&token_id), "name": "hex" })
+    );
+
+    for hex in ["abc", "zz"] {
+        let request = Request::new(query).variables(Variables::from_json(json!({ "hex": hex })));
+        let outcome = market
+            .chain
+            .try_graphql_query(market.application_id, request)
+            .await;
+        assert!(outcome.is_err(), "{hex:?} should not decode");
+    }
+}
+
+/// A minter of a collection can set its metadata first, becoming its creator, and later
+/// update it. Accounts without NFTs in a collection can't set its metadata.
+#[tokio::test(flavor = "multi_thread")]
+async fn collection_meta_can_be_set_by_a_minter() {
+    let market = Marketplace::new(Config::default()).await;
+    market
+        .mint(MintArgs {
+            collection: "apes",
+            ..MintArgs::new(market.signer(), "ape")
+        })
+        .await;
+    let set_meta = |collection: &str, description: &str| Operation::SetCollectionMeta {
+        collection: collection.to_owned(),
+        name: "Apes".to_owned(),
+        description: description.to_owned(),
+        banner_blob_hash: Some(market.blob_hash),
+    };
+
+    market.execute(set_meta("apes", "Bored")).await;
+    market.execute(set_meta("apes", "Very bored")).await;
+    assert!(market.rejects(set_meta("cats", "Not mine")).await);
+
+    let query = "query($collection: String!) { \
+        collectionMeta(collection: $collection) { name description bannerBlobHash creator } \
+    }";
+    let response = market.query(query, json!({ "collection": "apes" })).await;
+    assert_eq!(
+        response["collectionMeta"],
+        json!({
+            "name": "Apes",
+            "description": "Very bored",
+            "bannerBlobHash": market.blob_hash,
+            "creator": market.signer(),
+        })
+    );
+    let response = market.query(query, json!({ "collection": "cats" })).await;
+    assert!(response["collectionMeta"].is_null());
+}
+
+/// The Non-Fungible Token application on a single chain, with a deployed mock solver routing
+/// its swaps, and a published blob to use as the image of NFTs.
+struct Marketplace {
+    chain: ActiveChain,
+    application_id: ApplicationId<NonFungibleTokenAbi>,
+    solver_id: ApplicationId<UniversalSolverAbi>,
+    blob_hash: DataBlobHash,
+}
+
+impl Marketplace {
+    /// Creates the mock solver and the application with `config` on a new chain.
+    async fn new(config: Config) -> Self {
+        Self::with_config(|_signer| config).await
+    }
+
+    /// Creates the mock solver and the application on a new chain, with the configuration
+    /// built by `config` from the owner authenticating the chain's blocks.
+    async fn with_config(config: impl FnOnce(AccountOwner) -> Config) -> Self {
+        let (validator, bytecode_id) =
+            TestValidator::with_current_bytecode::<NonFungibleTokenAbi, Parameters, Config>().await;
+        let mut chain = validator.new_chain().await;
+        let config = config(AccountOwner::User(Owner::from(chain.public_key())));
+
+        let solver_bytecode_id = chain
+            .publish_bytecode_files_in::<UniversalSolverAbi, (), ()>("mock-solver")
+            .await;
+        let solver_id = chain
+            .create_application(solver_bytecode_id, (), (), vec![])
+            .await;
+
+        let parameters = Parameters {
+            universal_solver_id: solver_id,
+            limits: Default::default(),
+        };
+        let application_id = chain
+            .create_application(
+                bytecode_id,
+                parameters,
+                config,
+                vec![solver_id.forget_abi()],
+            )
+            .await;
+
+        let blob = Blob::new_data(b"image".to_vec());
+        let blob_hash = blob.id().hash;
+        chain
+            .add_block_with_blobs(
+                |block| {
+                    block.with_system_operation(SystemOperation::PublishDataBlob { blob_hash });
+                },
+                vec![blob],
+            )
+            .await;
+
+        Marketplace {
+            chain,
+            application_id,
+            solver_id,
+            blob_hash: DataBlobHash(blob_hash),
+        }
+    }
+
+    /// The owner authenticating the blocks of the chain.
+    fn signer(&self) -> AccountOwner {
+        AccountOwner::User(Owner::from(self.chain.public_key()))
+    }
+
+    fn account(&self, owner: AccountOwner) -> Account {
+        Account {
+            chain_id: self.chain.id(),
+            owner,
+        }
+    }
+
+    /// Executes `operation` in a new block, which must succeed.
+    async fn execute(&self, operation: Operation) {
+        self.chain
+            .add_block(|block| {
+                block.with_operation(self.application_id, operation);
+            })
+            .await;
+    }
+
+    /// Returns whether a block executing `operation` is rejected.
+    async fn rejects(&self, operation: Operation) -> bool {
+        self.chain
+            .try_add_block(|block| {
+                block.with_operation(self.application_id, operation);
+            })
+            .await
+            .is_err()
+    }
+
+    /// Mints an NFT and returns its ID.
+    async fn mint(&self, args: MintArgs) -> TokenId {
+        let name = args.name;
+        self.execute(self.mint_operation(args)).await;
+        self.token_id_by_name(name).await
+    }
+
+    fn mint_operation(&self, args: MintArgs) -> Operation {
+        Operation::Mint {
+            minter: args.minter,
+            name: args.name.to_owned(),
+            blob_hash: self.blob_hash,
+            token: args.token.to_owned(),
+            price: args.price.to_owned(),
+            id: 0,
+            chain_minter: args.chain_minter.to_owned(),
+            chain_owner: args.chain_owner.to_owned(),
+            description: args.description.to_owned(),
+            collection: args.collection.to_owned(),
+            royalty_bps: args.royalty_bps,
+            revealable: false,
+            list_immediately: args.list_immediately,
+            soulbound: args.soulbound,
+        }
+    }
+
+    /// A purchase of `token_id` by `owner(2)`, paying `amount` of `to_token` bought with USDC.
+    fn transfer(&self, token_id: &TokenId, amount: &str, to_token: &str) -> Operation {
+        Operation::Transfer {
+            source_owner: owner(1),
+            token_id: token_id.clone(),
+            target_account: self.account(owner(2)),
+            chain_owner: None,
+            buy_from_token: "USDC".to_owned(),
+            to_token: to_token.to_owned(),
+            amount: amount.to_owned(),
+            memo: None,
+            royalty_bps_override: None,
+        }
+    }
+
+    /// Runs a GraphQL `query` with `variables` against the application.
+    async fn query(&self, query: &str, variables: serde_json::Value) -> serde_json::Value {
+        let request = Request::new(query).variables(Variables::from_json(variables));
+        self.chain
+            .graphql_query(self.application_id, request)
+            .await
+            .response
+    }
+
+    /// Returns the NFT `token_id` as seen by the `nft` query.
+    async fn nft(&self, token_id: &TokenId) -> serde_json::Value {
+        let response = self
+            .query(
+                "query($tokenId: String!) { \
+                    nft(tokenId: $tokenId) { owner status token price chainOwner updatedAt } \
+                }",
+                json!({ "tokenId": encode(token_id) }),
+            )
+            .await;
+        response["nft"].clone()
+    }
+
+    /// Returns the `supplyStats` counters.
+    async fn supply_stats(&self) -> serde_json::Value {
+        let response = self
+            .query(
+                "query { supplyStats { everMinted currentlyActive burned } }",
+                json!({}),
+            )
+            .await;
+        response["supplyStats"].clone()
+    }
+
+    /// Returns the reporters and reasons of the reports filed against `token_id`.
+    async fn flags(&self, token_id: &TokenId) -> serde_json::Value {
+        let response = self
+            .query(
+                "query($tokenId: String!) { flags(tokenId: $tokenId) { reporter reason } }",
+                json!({ "tokenId": encode(token_id) }),
+            )
+            .await;
+        response["flags"].clone()
+    }
+
+    /// Returns the number of NFTs `owner` holds according to the owner index.
+    async fn owned_token_count(&self, owner: AccountOwner) -> usize {
+        let response = self
+            .query(
+                "query($owner: AccountOwner!) { ownedTokenIdsByOwner(owner: $owner) }",
+                json!({ "owner": owner }),
+            )
+            .await;
+        response["ownedTokenIdsByOwner"]
+            .as_array()
+            .expect("Token IDs should be a list")
+            .len()
+    }
+
+    /// Looks up the ID of the NFT named `name`.
+    async fn token_id_by_name(&self, name: &str) -> TokenId {
+        let response = self.query("query { nfts }", json!({})).await;
+        let token_id = response["nfts"]
+            .as_object()
+            .expect("NFTs should be a map")
+            .iter()
+            .find(|(_, nft)| nft["name"] == name)
+            .map(|(token_id, _)| token_id.clone())
+            .unwrap_or_else(|| panic!("NFT {name} not found"));
+        TokenId {
+            id: STANDARD_NO_PAD.decode(token_id).expect("Invalid token ID"),
+        }
+    }
+
+    /// Returns the amounts and destinations of the swaps requested from the solver so far.
+    async fn swaps(&self) -> Vec<(Amount, String)> {
+        let response = self
+            .chain
+            .graphql_query(
+                self.solver_id,
+                "query { swaps { amount destinationAddress } }",
+            )
+            .await
+            .response;
+        response["swaps"]
+            .as_array()
+            .expect("Swaps should be a list")
+            .iter()
+            .map(|swap| {
+                let amount = swap["amount"]
+                    .as_str()
+                    .and_then(|amount| amount.parse().ok())
+                    .expect("Swapped amount is not a valid amount");
+                let destination = swap["destinationAddress"]
+                    .as_str()
+                    .expect("Swap destination should be a string");
+                (amount, destination.to_owned())
+            })
+            .collect()
+    }
+}
+
+/// The fields of a mint that tests vary. The others are left empty.
+struct MintArgs {
+    minter: AccountOwner,
+    name: &'static str,
+    description: &'static str,
+    token: &'static str,
+    price: &'static str,
+    chain_minter: &'static str,
+    chain_owner: &'static str,
+    collection: &'static str,
+    royalty_bps: Option<u16>,
+    list_immediately: bool,
+    soulbound: bool,
+}
+
+impl MintArgs {
+    /// An unlisted NFT named `name`, minted by `minter` at 0.05 ETH.
+    fn new(minter: AccountOwner, name: &'static str) -> Self {
+        MintArgs {
+            minter,
+            name,
+            description: "",
+            token: "ETH",
+            price: "0.05",
+            chain_minter: "",
+            chain_owner: "",
+            collection: "",
+            royalty_bps: None,
+            list_immediately: false,
+            soulbound: false,
+        }
+    }
+}
+
+/// Encodes a token ID the way queries and mutations accept it.
+fn encode(token_id: &TokenId) -> String {
+    STANDARD_NO_PAD.encode(&token_id.id)
+}
+
+/// Returns the token IDs of a map of NFTs returned by a query, in order.
+fn token_ids(nfts: &serde_json::Value) -> Vec<String> {
+    nfts.as_object()
+        .expect("NFTs should be a map")
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Returns the accounts and volumes of a leaderboard returned by a query, in order.
+fn account_volumes(leaderboard: &serde_json::Value) -> Vec<(serde_json::Value, Amount)> {
+    leaderboard
+        .as_array()
+        .expect("Leaderboard should be a list")
+        .iter()
+        .map(|entry| (entry["account"].clone(), amount(&entry["volume"])))
+        .collect()
+}
+
+/// Parses an amount returned by a query.
+fn amount(value: &serde_json::Value) -> Amount {
+    value
+        .as_str()
+        .and_then(|amount| amount.parse().ok())
+        .unwrap_or_else(|| panic!("{value} is not a valid amount"))
+}
+
+/// An owner that never signs, for NFTs that are only received.
+fn owner(seed: u64) -> AccountOwner {
+    AccountOwner::User(Owner(CryptoHash::from([seed; 4])))
+}